@@ -0,0 +1,407 @@
+//! The library browser: a filterable list of writings with a preview and
+//! their details, shown when unveilox is started without a writing.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::config::Settings;
+use crate::fuzzy;
+use crate::keys::KeyBinding;
+use crate::library::{Entry, Library, Skipped};
+use crate::reveal;
+use crate::theme::Theme;
+use crate::viewport;
+use crate::writing::Writing;
+
+/// Rows moved by PgUp/PgDn in the list.
+const PAGE_ROWS: usize = 10;
+
+/// A writing together with where it was found and how long it takes.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub entry: Entry,
+    pub writing: Writing,
+    /// The length of its reveal at the current settings.
+    pub duration: Duration,
+}
+
+impl Item {
+    /// Whether every word of `filter` appears in the name, title, author or
+    /// tags, ignoring case.
+    fn matches(&self, filter: &str) -> bool {
+        let mut haystack = format!("{} {}", self.entry.name, self.writing.title());
+        if let Some(author) = &self.writing.author {
+            haystack.push(' ');
+            haystack.push_str(author);
+        }
+        for tag in &self.writing.tags {
+            haystack.push(' ');
+            haystack.push_str(tag);
+        }
+        let haystack = haystack.to_lowercase();
+        filter
+            .split_whitespace()
+            .all(|word| haystack.contains(&word.to_lowercase()))
+    }
+}
+
+/// What the user asked for with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    /// Unveil the selected writing, then come back.
+    Play,
+    Quit,
+}
+
+#[derive(Debug, Clone)]
+pub struct Browser {
+    items: Vec<Item>,
+    filter: String,
+    /// Whether typed characters go to the filter box.
+    filtering: bool,
+    /// Indices into `items` that pass the filter.
+    shown: Vec<usize>,
+    /// Position within `shown`.
+    selected: usize,
+}
+
+impl Browser {
+    pub fn new(items: Vec<Item>) -> Self {
+        let shown = (0..items.len()).collect();
+        Self {
+            items,
+            filter: String::new(),
+            filtering: false,
+            shown,
+            selected: 0,
+        }
+    }
+
+    /// Every writing in `library`, in name order, and those that failed to
+    /// load.
+    pub fn load(library: &Library, settings: &Settings) -> Result<(Self, Vec<Skipped>)> {
+        let (writings, skipped) = library.read_all()?;
+        let items = writings
+            .into_iter()
+            .map(|(entry, writing)| Item {
+                duration: settings.reveal_duration(&writing),
+                entry,
+                writing,
+            })
+            .collect();
+        Ok((Self::new(items), skipped))
+    }
+
+    pub fn selected(&self) -> Option<&Item> {
+        self.shown
+            .get(self.selected)
+            .map(|&index| &self.items[index])
+    }
+
+    fn refilter(&mut self) {
+        let current = self.shown.get(self.selected).copied();
+        self.shown = (0..self.items.len())
+            .filter(|&index| self.items[index].matches(&self.filter))
+            .collect();
+        // Keep the same writing selected when it still passes.
+        self.selected = current
+            .and_then(|current| self.shown.iter().position(|&index| index == current))
+            .unwrap_or(0);
+    }
+
+    fn select(&mut self, position: usize) {
+        self.selected = position.min(self.shown.len().saturating_sub(1));
+    }
+
+    /// Applies a key press, returning what to do when it ends browsing.
+    pub fn handle(&mut self, key: &KeyEvent, exit_keys: &[KeyBinding]) -> Option<Choice> {
+        let typed = !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select(self.selected + 1),
+            KeyCode::PageUp => self.select(self.selected.saturating_sub(PAGE_ROWS)),
+            KeyCode::PageDown => self.select(self.selected + PAGE_ROWS),
+            KeyCode::Home => self.select(0),
+            KeyCode::End => self.select(usize::MAX),
+            KeyCode::Enter => {
+                self.filtering = false;
+                return self.selected().is_some().then_some(Choice::Play);
+            }
+            KeyCode::Esc if self.filtering => {
+                self.filtering = false;
+                self.filter.clear();
+                self.refilter();
+            }
+            KeyCode::Backspace if self.filtering => {
+                self.filter.pop();
+                self.refilter();
+            }
+            KeyCode::Char(ch) if self.filtering && typed => {
+                self.filter.push(ch);
+                self.refilter();
+            }
+            KeyCode::Char('/') => self.filtering = true,
+            KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Char('j') => self.select(self.selected + 1),
+            _ if exit_keys.iter().any(|binding| binding.matches(key)) => return Some(Choice::Quit),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn draw(&self, frame: &mut Frame, theme: &Theme, exit_keys: &[KeyBinding]) {
+        let base = theme.base_style();
+        let dim = base.add_modifier(Modifier::DIM);
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(4),
+                Constraint::Length(1),
+            ])
+            .split(frame.size());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(outer[1]);
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(8)])
+            .split(columns[1]);
+
+        self.draw_filter(frame, outer[0], base, dim);
+        self.draw_list(frame, columns[0], base);
+        if let Some(item) = self.selected() {
+            draw_preview(frame, right[0], &item.writing, theme);
+            draw_details(frame, right[1], item, base);
+        } else {
+            let empty = Paragraph::new("No writing matches the filter.")
+                .style(dim)
+                .block(Block::default().borders(Borders::ALL).style(base));
+            frame.render_widget(empty, columns[1]);
+        }
+
+        let status = self.status_line(exit_keys);
+        frame.render_widget(Paragraph::new(status).style(dim), outer[2]);
+    }
+
+    /// The keys that apply, quitting with the first of `exit_keys`.
+    fn status_line(&self, exit_keys: &[KeyBinding]) -> String {
+        if self.filtering {
+            return "type to filter · ↑/↓ choose · enter play · esc clear".to_string();
+        }
+        let mut line = "↑/↓ choose · enter play · / filter".to_string();
+        if let Some(exit) = exit_keys.first() {
+            line.push_str(&format!(" · {exit} quit"));
+        }
+        line
+    }
+
+    fn draw_filter(&self, frame: &mut Frame, area: Rect, base: Style, dim: Style) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Filter")
+            .style(base);
+        let inner = block.inner(area);
+        let text = if self.filter.is_empty() && !self.filtering {
+            Line::styled("press / to filter by name, title, author or tag", dim)
+        } else {
+            Line::raw(self.filter.as_str())
+        };
+        frame.render_widget(Paragraph::new(text).block(block), area);
+
+        if self.filtering {
+            let typed = u16::try_from(unicode_width::UnicodeWidthStr::width(self.filter.as_str()))
+                .unwrap_or(u16::MAX);
+            let x = inner
+                .x
+                .saturating_add(typed)
+                .min(inner.right().saturating_sub(1));
+            frame.set_cursor(x, inner.y);
+        }
+    }
+
+    fn draw_list(&self, frame: &mut Frame, area: Rect, base: Style) {
+        let items: Vec<ListItem> = self
+            .shown
+            .iter()
+            .map(|&index| {
+                let item = &self.items[index];
+                let title = item.writing.title();
+                let mut line = vec![Span::raw(title.to_string())];
+                if fuzzy::normalize(title) != fuzzy::normalize(&item.entry.name) {
+                    line.push(Span::styled(
+                        format!("  {}", item.entry.name),
+                        base.add_modifier(Modifier::DIM),
+                    ));
+                }
+                ListItem::new(Line::from(line))
+            })
+            .collect();
+        let title = format!("Writings ({}/{})", self.shown.len(), self.items.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .style(base)
+            .highlight_style(base.add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(self.selected().map(|_| self.selected));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+/// As much of the text as fits, laid out the way the reveal will show it.
+fn draw_preview(frame: &mut Frame, area: Rect, writing: &Writing, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Preview")
+        .style(theme.base_style());
+    let inner = block.inner(area);
+    let rows = viewport::wrap(&writing.body, inner.width.into());
+    let shown = &rows[..rows.len().min(inner.height.into())];
+    let preview =
+        Paragraph::new(theme.styled_rows(&writing.body, &writing.markup, shown)).block(block);
+    frame.render_widget(preview, area);
+}
+
+fn draw_details(frame: &mut Frame, area: Rect, item: &Item, base: Style) {
+    let writing = &item.writing;
+    let field = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{label:<9}"), base.add_modifier(Modifier::BOLD)),
+            Span::raw(value),
+        ])
+    };
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let lines = vec![
+        field("Author", or_dash(writing.author.clone())),
+        field("Year", or_dash(writing.year.map(|year| year.to_string()))),
+        field("Tags", writing.tags.join(", ")),
+        field("Language", or_dash(writing.language.clone())),
+        field(
+            "Length",
+            format!(
+                "{} lines · {}",
+                writing.line_count(),
+                reveal::reading_time(item.duration)
+            ),
+        ),
+        field("Source", item.entry.source.to_string()),
+    ];
+    let details = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(writing.title().to_string())
+            .style(base),
+    );
+    frame.render_widget(details, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::{Entry, Source};
+    use crossterm::event::KeyEvent;
+
+    fn item(name: &str, text: &str) -> Item {
+        let entry = Entry {
+            name: name.to_string(),
+            source: Source::Bundled,
+            shadows: Vec::new(),
+        };
+        let writing = Writing::parse(name, text).unwrap();
+        Item {
+            duration: Settings::default().reveal_duration(&writing),
+            entry,
+            writing,
+        }
+    }
+
+    fn browser() -> Browser {
+        Browser::new(vec![
+            item(
+                "if",
+                "---\ntitle: If—\nauthor: Rudyard Kipling\ntags: [advice]\n---\nIf you can\n",
+            ),
+            item(
+                "invictus",
+                "---\nauthor: William Ernest Henley\n---\nOut of the night\n",
+            ),
+            item("ozymandias", "I met a traveller\n"),
+        ])
+    }
+
+    fn press(browser: &mut Browser, keys: &str) -> Option<Choice> {
+        let exit_keys = KeyBinding::default_exit_keys();
+        let mut choice = None;
+        for ch in keys.chars() {
+            let code = match ch {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                '↓' => KeyCode::Down,
+                '↑' => KeyCode::Up,
+                ch => KeyCode::Char(ch),
+            };
+            choice = browser.handle(&KeyEvent::from(code), &exit_keys);
+        }
+        choice
+    }
+
+    fn selected(browser: &Browser) -> &str {
+        &browser.selected().unwrap().entry.name
+    }
+
+    #[test]
+    fn keys_move_the_selection_within_bounds() {
+        let mut browser = browser();
+        press(&mut browser, "↑");
+        assert_eq!(selected(&browser), "if");
+        press(&mut browser, "↓↓↓↓");
+        assert_eq!(selected(&browser), "ozymandias");
+        press(&mut browser, "k");
+        assert_eq!(selected(&browser), "invictus");
+        assert_eq!(press(&mut browser, "\n"), Some(Choice::Play));
+        assert_eq!(press(&mut browser, "q"), Some(Choice::Quit));
+    }
+
+    #[test]
+    fn filter_matches_every_word_in_any_field() {
+        let mut browser = browser();
+        press(&mut browser, "/henley");
+        assert_eq!(browser.shown, [1]);
+        assert_eq!(selected(&browser), "invictus");
+
+        // While filtering, letters are text rather than commands.
+        press(&mut browser, "\x08\x08\x08\x08\x08\x08advice q");
+        assert!(browser.shown.is_empty());
+        assert_eq!(press(&mut browser, "\n"), None);
+
+        press(&mut browser, "/\x08\x08");
+        assert_eq!(browser.shown, [0]);
+        press(&mut browser, "\x1b");
+        assert_eq!(browser.shown, [0, 1, 2]);
+        assert_eq!(selected(&browser), "if");
+    }
+
+    #[test]
+    fn status_line_names_the_configured_exit_key() {
+        let mut browser = browser();
+        let exit_keys = vec!["x".parse().unwrap()];
+        assert!(browser
+            .status_line(&exit_keys)
+            .ends_with("/ filter · x quit"));
+        assert!(browser.status_line(&[]).ends_with("/ filter"));
+        press(&mut browser, "/");
+        assert!(browser.status_line(&exit_keys).ends_with("esc clear"));
+    }
+}
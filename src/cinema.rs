@@ -0,0 +1,216 @@
+//! The TUI's bookends: an opening title sequence that fades in at the
+//! centre of the screen, and closing credits that scroll up movie-style.
+//!
+//! A [`Sequence`] is a list of timed frames. It only knows how to draw
+//! each one; the engine shows them, on the terminal or into a recording,
+//! and lets a key skip the rest.
+
+use std::time::Duration;
+
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::export;
+use crate::theme::Theme;
+use crate::writing::Writing;
+
+/// How long the title takes to fade in.
+pub const FADE_IN: Duration = Duration::from_millis(1200);
+/// Steps of the fade, after the blank first frame.
+const FADE_STEPS: u32 = 12;
+/// How long the credits take to move up a row.
+pub const CREDITS_ROW: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Fades in, then stays up for the hold.
+    Opening { hold: Duration },
+    /// Rises from below the screen until it has left at the top.
+    Credits,
+}
+
+/// A title sequence or the end credits of a writing.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    kind: Kind,
+    lines: Vec<(String, Modifier)>,
+    base: Style,
+    /// Foreground and background, for the fade; `None` when colour is off
+    /// and the fade is done by dimming.
+    colors: Option<([u8; 3], [u8; 3])>,
+}
+
+impl Sequence {
+    /// The title, author and year of `writing`, faded in and then held
+    /// for `hold`.
+    pub fn opening(writing: &Writing, theme: &Theme, hold: Duration) -> Self {
+        let mut lines = vec![(writing.title().to_string(), Modifier::BOLD)];
+        if let Some(author) = &writing.author {
+            lines.push((author.clone(), Modifier::ITALIC));
+        }
+        if let Some(year) = writing.year {
+            lines.push((year.to_string(), Modifier::DIM));
+        }
+        Self::new(Kind::Opening { hold }, lines, theme)
+    }
+
+    /// Credits for `writing`: its title, author, year and license.
+    pub fn credits(writing: &Writing, theme: &Theme) -> Self {
+        let mut lines = vec![(writing.title().to_string(), Modifier::BOLD)];
+        if let Some(author) = &writing.author {
+            lines.push((String::new(), Modifier::empty()));
+            lines.push(("written by".to_string(), Modifier::DIM));
+            lines.push((author.clone(), Modifier::empty()));
+        }
+        if let Some(year) = writing.year {
+            lines.push((String::new(), Modifier::empty()));
+            lines.push((year.to_string(), Modifier::empty()));
+        }
+        if let Some(license) = &writing.license {
+            lines.push((String::new(), Modifier::empty()));
+            lines.push((license.clone(), Modifier::DIM));
+        }
+        lines.push((String::new(), Modifier::empty()));
+        lines.push((String::new(), Modifier::empty()));
+        lines.push(("unveiled with unveilox".to_string(), Modifier::ITALIC));
+        Self::new(Kind::Credits, lines, theme)
+    }
+
+    fn new(kind: Kind, lines: Vec<(String, Modifier)>, theme: &Theme) -> Self {
+        Self {
+            kind,
+            lines,
+            base: theme.base_style(),
+            colors: theme.colored.then(|| export::colors(theme, None)),
+        }
+    }
+
+    /// How long each frame stays up on a screen `height` rows tall.
+    pub fn frames(&self, height: u16) -> Vec<Duration> {
+        match self.kind {
+            Kind::Opening { hold } => {
+                let mut frames = vec![FADE_IN / FADE_STEPS; FADE_STEPS as usize];
+                frames.push(hold);
+                frames
+            }
+            Kind::Credits => {
+                let rows = usize::from(height) + self.lines.len();
+                let mut frames = vec![CREDITS_ROW; rows];
+                frames.push(Duration::ZERO);
+                frames
+            }
+        }
+    }
+
+    /// Draws `frame` over the whole screen.
+    pub fn draw(&self, f: &mut Frame, frame: usize) {
+        let area = f.size();
+        f.render_widget(Block::default().style(self.base), area);
+
+        let (first, style) = match self.kind {
+            Kind::Opening { .. } => {
+                // The title sits just above the middle, as on the title cards.
+                let first = i32::from(area.height / 2) - 1;
+                (first, self.faded(frame as f32 / FADE_STEPS as f32))
+            }
+            Kind::Credits => (i32::from(area.height) - frame as i32, self.base),
+        };
+        for (index, (line, modifier)) in self.lines.iter().enumerate() {
+            let y = first + index as i32;
+            let Ok(y) = u16::try_from(y) else {
+                continue;
+            };
+            if y >= area.height {
+                continue;
+            }
+            let row = Rect::new(area.x, area.y + y, area.width, 1);
+            let paragraph = Paragraph::new(line.as_str())
+                .style(style.add_modifier(*modifier))
+                .alignment(Alignment::Center);
+            f.render_widget(paragraph, row);
+        }
+    }
+
+    /// The text style `amount` of the way through the fade.
+    fn faded(&self, amount: f32) -> Style {
+        let amount = amount.min(1.0);
+        match self.colors {
+            Some((foreground, background)) => {
+                let [r, g, b] = export::blend(background, foreground, amount);
+                self.base.fg(Color::Rgb(r, g, b))
+            }
+            // Without colour: hidden, then dim, then as is.
+            None if amount < 1.0 / 3.0 => self.base.add_modifier(Modifier::HIDDEN),
+            None if amount < 1.0 => self.base.add_modifier(Modifier::DIM),
+            None => self.base,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writing() -> Writing {
+        Writing::parse(
+            "x",
+            "---\ntitle: Invictus\nauthor: W. E. Henley\nyear: 1888\n---\nOut of the night\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn the_opening_fades_in_then_holds() {
+        let opening = Sequence::opening(&writing(), &Theme::default(), Duration::from_secs(3));
+        let frames = opening.frames(24);
+        assert_eq!(frames.len(), FADE_STEPS as usize + 1);
+        assert_eq!(
+            frames.iter().sum::<Duration>(),
+            FADE_IN + Duration::from_secs(3)
+        );
+
+        let (foreground, background) = opening.colors.unwrap();
+        let [r, g, b] = background;
+        assert_eq!(opening.faded(0.0).fg, Some(Color::Rgb(r, g, b)));
+        let [r, g, b] = foreground;
+        assert_eq!(opening.faded(1.0).fg, Some(Color::Rgb(r, g, b)));
+
+        let plain = Sequence::opening(
+            &writing(),
+            &Theme::default().without_color(),
+            Duration::ZERO,
+        );
+        assert!(plain.faded(0.0).add_modifier.contains(Modifier::HIDDEN));
+        assert!(plain.faded(0.5).add_modifier.contains(Modifier::DIM));
+        assert_eq!(plain.faded(1.0), plain.base);
+    }
+
+    #[test]
+    fn credits_scroll_until_they_have_left_the_screen() {
+        let credits = Sequence::credits(&writing(), &Theme::default());
+        let lines: Vec<_> = credits
+            .lines
+            .iter()
+            .map(|(line, _)| line.as_str())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Invictus",
+                "",
+                "written by",
+                "W. E. Henley",
+                "",
+                "1888",
+                "",
+                "",
+                "unveiled with unveilox"
+            ]
+        );
+        assert_eq!(credits.frames(10).len(), 10 + lines.len() + 1);
+    }
+}
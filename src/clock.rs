@@ -0,0 +1,141 @@
+//! Where a live reveal gets the time and its key presses from.
+//!
+//! On the terminal that is [`SystemClock`] and [`TerminalEvents`]. Tests use
+//! a [`VirtualClock`] instead, which only moves when [`ScriptedEvents`] waits
+//! on it, so a whole reveal plays in microseconds and always the same way.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use crossterm::event::{self, Event};
+
+/// The time since a reveal began.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+/// Key presses, mouse and resize events.
+pub trait EventSource {
+    /// The next event, if one comes within `timeout`.
+    fn poll(&mut self, timeout: Duration) -> Result<Option<Event>>;
+}
+
+/// The wall clock, counted from when it was made.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// The terminal's own events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        Ok(if event::poll(timeout)? {
+            Some(event::read()?)
+        } else {
+            None
+        })
+    }
+}
+
+/// A clock that stands still until moved. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock on to `at`, or leaves it if it is already later.
+    pub fn advance_to(&self, at: Duration) {
+        self.now.set(self.now.get().max(at));
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+/// Events due at set times on a [`VirtualClock`]. Waiting for one moves the
+/// clock on to it, or by the whole timeout when none is due by then.
+#[derive(Debug, Clone)]
+pub struct ScriptedEvents {
+    clock: VirtualClock,
+    script: VecDeque<(Duration, Event)>,
+}
+
+impl ScriptedEvents {
+    /// `script` is a list of events and when they happen, in order.
+    pub fn new(clock: &VirtualClock, script: impl IntoIterator<Item = (Duration, Event)>) -> Self {
+        Self {
+            clock: clock.clone(),
+            script: script.into_iter().collect(),
+        }
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn poll(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        let deadline = self.clock.now() + timeout;
+        match self.script.front() {
+            Some(&(at, _)) if at <= deadline => {
+                self.clock.advance_to(at);
+                Ok(self.script.pop_front().map(|(_, event)| event))
+            }
+            _ => {
+                self.clock.advance_to(deadline);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn scripted_events_move_the_virtual_clock() {
+        let clock = VirtualClock::new();
+        let key = Event::Key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+        let mut events = ScriptedEvents::new(&clock, [(Duration::from_millis(150), key.clone())]);
+
+        assert_eq!(events.poll(Duration::from_millis(100)).unwrap(), None);
+        assert_eq!(clock.now(), Duration::from_millis(100));
+        assert_eq!(events.poll(Duration::from_millis(100)).unwrap(), Some(key));
+        assert_eq!(clock.now(), Duration::from_millis(150));
+        assert_eq!(events.poll(Duration::from_secs(1)).unwrap(), None);
+        assert_eq!(clock.now(), Duration::from_millis(1150));
+    }
+}
@@ -0,0 +1,488 @@
+//! User configuration in `~/.config/unveilox/config.toml`.
+//!
+//! Settings are layered: built-in defaults, then the top level of the config
+//! file, then the selected profile, then command-line flags.
+//!
+//! ```toml
+//! speed = 30
+//! profile = "stage"        # used when --profile is not given
+//!
+//! [profiles.stage]
+//! mode = "tui"             # typewriter | tui | plain
+//! unit = "line"            # char | word | line | stanza
+//! theme = "high-contrast"
+//! exit_keys = ["esc", "ctrl+c"]
+//! credits = true           # end credits after a TUI reveal
+//!
+//! [pauses]                 # extra milliseconds; unset ones fall through
+//! comma = 120              # also ; and :
+//! period = 300             # also ! ? and …
+//! dash = 200
+//! line = 250
+//! stanza = 800
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::keys::KeyBinding;
+use crate::reveal::{Pauses, Progress, Unit};
+use crate::writing::Writing;
+
+/// Milliseconds per character unless configured otherwise.
+pub const DEFAULT_SPEED: u64 = 25;
+pub const MIN_SPEED: u64 = 1;
+pub const MAX_SPEED: u64 = 1_000;
+pub const DEFAULT_THEME: &str = "default";
+/// The longest configurable pause, in milliseconds.
+pub const MAX_PAUSE: u64 = 10_000;
+
+/// Checks that `speed` lies between [`MIN_SPEED`] and [`MAX_SPEED`].
+pub fn validate_speed(speed: u64) -> std::result::Result<u64, String> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        Err(format!(
+            "speed must be between {MIN_SPEED} and {MAX_SPEED} milliseconds"
+        ))
+    } else {
+        Ok(speed)
+    }
+}
+
+/// How a writing is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Typewriter,
+    Tui,
+    /// Text streamed without escape sequences, for pipes and logs.
+    Plain,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Typewriter => "typewriter",
+            Mode::Tui => "tui",
+            Mode::Plain => "plain",
+        })
+    }
+}
+
+/// Pause lengths from one layer; unset ones fall through.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PauseTable {
+    pub comma: Option<u64>,
+    pub period: Option<u64>,
+    pub dash: Option<u64>,
+    pub line: Option<u64>,
+    pub stanza: Option<u64>,
+}
+
+impl PauseTable {
+    /// Every pause set to nothing.
+    pub fn off() -> Self {
+        Self {
+            comma: Some(0),
+            period: Some(0),
+            dash: Some(0),
+            line: Some(0),
+            stanza: Some(0),
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, Option<u64>); 5] {
+        [
+            ("comma", self.comma),
+            ("period", self.period),
+            ("dash", self.dash),
+            ("line", self.line),
+            ("stanza", self.stanza),
+        ]
+    }
+
+    fn over(&self, below: Pauses) -> Pauses {
+        Pauses {
+            comma: self.comma.unwrap_or(below.comma),
+            period: self.period.unwrap_or(below.period),
+            dash: self.dash.unwrap_or(below.dash),
+            line: self.line.unwrap_or(below.line),
+            stanza: self.stanza.unwrap_or(below.stanza),
+        }
+    }
+}
+
+/// One layer of presentation settings; unset fields fall through.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Profile {
+    pub speed: Option<u64>,
+    pub mode: Option<Mode>,
+    pub unit: Option<Unit>,
+    pub theme: Option<String>,
+    pub exit_keys: Option<Vec<KeyBinding>>,
+    pub pauses: Option<PauseTable>,
+    pub credits: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    profile: Option<String>,
+    speed: Option<u64>,
+    mode: Option<Mode>,
+    unit: Option<Unit>,
+    theme: Option<String>,
+    exit_keys: Option<Vec<KeyBinding>>,
+    pauses: Option<PauseTable>,
+    credits: Option<bool>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Where an effective setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    Config,
+    Profile(String),
+    CommandLine,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.write_str("default"),
+            Origin::Config => f.write_str("config"),
+            Origin::Profile(name) => write!(f, "profile {name}"),
+            Origin::CommandLine => f.write_str("command line"),
+        }
+    }
+}
+
+/// A setting's value and the layer it came from.
+#[derive(Debug, Clone)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub origin: Origin,
+}
+
+impl<T> Sourced<T> {
+    fn new(value: T, origin: Origin) -> Self {
+        Self { value, origin }
+    }
+
+    fn overlay(&mut self, value: Option<T>, origin: &Origin) {
+        if let Some(value) = value {
+            *self = Self::new(value, origin.clone());
+        }
+    }
+}
+
+/// Values given on the command line, which beat everything else.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub speed: Option<u64>,
+    pub mode: Option<Mode>,
+    pub unit: Option<Unit>,
+    pub theme: Option<String>,
+    /// `--no-pauses`.
+    pub pauses: Option<PauseTable>,
+    /// `--credits`.
+    pub credits: Option<bool>,
+}
+
+/// The merged, effective presentation settings.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub profile: Option<String>,
+    pub speed: Sourced<u64>,
+    pub mode: Sourced<Mode>,
+    pub unit: Sourced<Unit>,
+    pub theme: Sourced<String>,
+    pub exit_keys: Sourced<Vec<KeyBinding>>,
+    pub pauses: Sourced<Pauses>,
+    /// Whether a TUI reveal ends with scrolling credits.
+    pub credits: Sourced<bool>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            speed: Sourced::new(DEFAULT_SPEED, Origin::Default),
+            mode: Sourced::new(Mode::Typewriter, Origin::Default),
+            unit: Sourced::new(Unit::default(), Origin::Default),
+            theme: Sourced::new(DEFAULT_THEME.to_string(), Origin::Default),
+            exit_keys: Sourced::new(KeyBinding::default_exit_keys(), Origin::Default),
+            pauses: Sourced::new(Pauses::RECITED, Origin::Default),
+            credits: Sourced::new(false, Origin::Default),
+        }
+    }
+}
+
+impl Settings {
+    /// How long `writing` takes to unveil at these settings, pauses and
+    /// markup included, when no key is pressed.
+    pub fn reveal_duration(&self, writing: &Writing) -> Duration {
+        Progress::new(&writing.body, self.unit.value, self.speed.value)
+            .with_pauses(self.pauses.value)
+            .with_markup(&writing.markup)
+            .duration()
+    }
+
+    fn apply(&mut self, layer: Profile, origin: Origin) -> Result<()> {
+        if let Some(speed) = layer.speed {
+            validate_speed(speed).map_err(anyhow::Error::msg)?;
+        }
+        if layer.exit_keys.as_ref().is_some_and(Vec::is_empty) {
+            bail!("exit_keys must list at least one key");
+        }
+        if let Some(pauses) = &layer.pauses {
+            for (name, pause) in pauses.fields() {
+                if pause.is_some_and(|pause| pause > MAX_PAUSE) {
+                    bail!("pauses.{name} must be at most {MAX_PAUSE} milliseconds");
+                }
+            }
+        }
+
+        self.speed.overlay(layer.speed, &origin);
+        self.mode.overlay(layer.mode, &origin);
+        self.unit.overlay(layer.unit, &origin);
+        self.theme.overlay(layer.theme, &origin);
+        self.exit_keys.overlay(layer.exit_keys, &origin);
+        // Pauses merge one by one, so a profile can change just the stanza break.
+        let pauses = layer.pauses.map(|table| table.over(self.pauses.value));
+        self.pauses.overlay(pauses, &origin);
+        self.credits.overlay(layer.credits, &origin);
+        Ok(())
+    }
+}
+
+/// The parsed config file, ready to be [resolved](Self::resolve) into
+/// [`Settings`].
+#[derive(Debug, Default)]
+pub struct Config {
+    /// The file the config was (or would have been) read from.
+    pub path: Option<PathBuf>,
+    /// Whether `path` existed.
+    pub loaded: bool,
+    file: ConfigFile,
+}
+
+impl Config {
+    /// Reads `explicit`, or the default config file if it exists.
+    pub fn load(explicit: Option<&Path>) -> Result<Self> {
+        let Some(path) = explicit.map(Path::to_path_buf).or_else(default_path) else {
+            return Ok(Self::default());
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && explicit.is_none() => {
+                return Ok(Self {
+                    path: Some(path),
+                    ..Self::default()
+                });
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+
+        let file = Self::parse(&text).with_context(|| format!("in config {}", path.display()))?;
+        Ok(Self {
+            path: Some(path),
+            loaded: true,
+            file,
+        })
+    }
+
+    fn parse(text: &str) -> Result<ConfigFile> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// The profiles the file defines, in name order.
+    pub fn profile_names(&self) -> impl Iterator<Item = &str> {
+        self.file.profiles.keys().map(String::as_str)
+    }
+
+    /// Merges defaults, the config file, `profile` (or the config's default
+    /// profile) and the command-line `overrides`.
+    pub fn resolve(&self, profile: Option<&str>, overrides: Overrides) -> Result<Settings> {
+        let mut settings = Settings::default();
+
+        let file = &self.file;
+        settings.apply(
+            Profile {
+                speed: file.speed,
+                mode: file.mode,
+                unit: file.unit,
+                theme: file.theme.clone(),
+                exit_keys: file.exit_keys.clone(),
+                pauses: file.pauses,
+                credits: file.credits,
+            },
+            Origin::Config,
+        )?;
+
+        if let Some(name) = profile.or(file.profile.as_deref()) {
+            let Some(layer) = file.profiles.get(name) else {
+                let known: Vec<_> = self.profile_names().collect();
+                if known.is_empty() {
+                    bail!("Unknown profile `{name}`: no profiles are defined");
+                }
+                bail!("Unknown profile `{name}` (available: {})", known.join(", "));
+            };
+            settings
+                .apply(layer.clone(), Origin::Profile(name.to_string()))
+                .with_context(|| format!("in profile `{name}`"))?;
+            settings.profile = Some(name.to_string());
+        }
+
+        settings.apply(
+            Profile {
+                speed: overrides.speed,
+                mode: overrides.mode,
+                unit: overrides.unit,
+                theme: overrides.theme,
+                exit_keys: None,
+                pauses: overrides.pauses,
+                credits: overrides.credits,
+            },
+            Origin::CommandLine,
+        )?;
+
+        Ok(settings)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/unveilox`, falling back to `~/.config/unveilox`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("unveilox"))
+}
+
+/// `config.toml` inside [`config_dir`].
+pub(crate) fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(text: &str) -> Config {
+        Config {
+            path: None,
+            loaded: true,
+            file: Config::parse(text).expect("config should parse"),
+        }
+    }
+
+    const SAMPLE: &str = r#"
+        speed = 40
+        theme = "mono"
+
+        [profiles.stage]
+        speed = 80
+        mode = "tui"
+        unit = "word"
+        exit_keys = ["esc", "ctrl+c"]
+    "#;
+
+    #[test]
+    fn layers_apply_in_order() {
+        let config = config(SAMPLE);
+
+        let base = config.resolve(None, Overrides::default()).unwrap();
+        assert_eq!(base.speed.value, 40);
+        assert_eq!(base.speed.origin, Origin::Config);
+        assert_eq!(base.mode.value, Mode::Typewriter);
+        assert_eq!(base.mode.origin, Origin::Default);
+
+        let stage = config
+            .resolve(
+                Some("stage"),
+                Overrides {
+                    speed: Some(10),
+                    ..Overrides::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(stage.profile.as_deref(), Some("stage"));
+        assert_eq!(stage.speed.value, 10);
+        assert_eq!(stage.speed.origin, Origin::CommandLine);
+        assert_eq!(stage.mode.value, Mode::Tui);
+        assert_eq!(stage.unit.value, Unit::Word);
+        assert_eq!(stage.mode.origin, Origin::Profile("stage".to_string()));
+        assert_eq!(stage.theme.value, "mono");
+        assert_eq!(stage.exit_keys.value.len(), 2);
+    }
+
+    #[test]
+    fn default_profile_comes_from_config() {
+        let config =
+            config("profile = \"stage\"\n[profiles.stage]\nmode = \"tui\"\ncredits = true\n");
+        let settings = config.resolve(None, Overrides::default()).unwrap();
+        assert_eq!(settings.mode.value, Mode::Tui);
+        assert!(settings.credits.value);
+        assert!(!Settings::default().credits.value);
+    }
+
+    #[test]
+    fn pauses_merge_field_by_field() {
+        let layered =
+            config("[pauses]\ncomma = 50\nline = 400\n[profiles.fast]\npauses = { line = 0 }\n");
+        let base = layered.resolve(None, Overrides::default()).unwrap();
+        assert_eq!(base.pauses.value.comma, 50);
+        assert_eq!(base.pauses.value.stanza, Pauses::RECITED.stanza);
+        assert_eq!(base.pauses.origin, Origin::Config);
+
+        let fast = layered.resolve(Some("fast"), Overrides::default()).unwrap();
+        assert_eq!(fast.pauses.value.line, 0);
+        assert_eq!(fast.pauses.value.comma, 50);
+
+        let off = layered
+            .resolve(
+                None,
+                Overrides {
+                    pauses: Some(PauseTable::off()),
+                    ..Overrides::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(off.pauses.value, Pauses::default());
+
+        assert!(Config::parse("[pauses]\nsemicolon = 1").is_err());
+        let too_long = config("[pauses]\nstanza = 60000");
+        assert!(too_long.resolve(None, Overrides::default()).is_err());
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        assert!(Config::parse("colour = \"red\"").is_err());
+        assert!(Config::parse("[profiles.x]\nmode = \"cinema\"").is_err());
+        assert!(Config::parse("[profiles.x]\nmode = \"plain\"").is_ok());
+        assert!(Config::parse("exit_keys = [\"hyper+q\"]").is_err());
+
+        let err = config(SAMPLE)
+            .resolve(Some("missing"), Overrides::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("available: stage"));
+
+        let too_fast = config("speed = 0");
+        assert!(too_fast.resolve(None, Overrides::default()).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Keys that steer a reveal while it plays, and the status line that shows
+//! their effect.
+//!
+//! Exit and playlist keys are checked before these, so a config that binds
+//! e.g. `s` as an exit key keeps working.
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::keys::KeyBinding;
+use crate::reveal::Progress;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    TogglePause,
+    Faster,
+    Slower,
+    FinishStanza,
+    RevealAll,
+}
+
+/// Space pauses, `+`/`-` change speed, → or Tab finish the stanza and `s`
+/// shows everything.
+pub fn control(key: &KeyEvent) -> Option<Control> {
+    match key.code {
+        KeyCode::Char(' ') => Some(Control::TogglePause),
+        KeyCode::Char('+' | '=') => Some(Control::Faster),
+        KeyCode::Char('-' | '_') => Some(Control::Slower),
+        KeyCode::Right | KeyCode::Tab => Some(Control::FinishStanza),
+        KeyCode::Char('s') => Some(Control::RevealAll),
+        _ => None,
+    }
+}
+
+/// Applies `control`, returning any text it revealed.
+pub fn apply<'a>(progress: &mut Progress<'a>, control: Control) -> &'a str {
+    match control {
+        Control::TogglePause => progress.toggle_pause(),
+        Control::Faster => progress.faster(),
+        Control::Slower => progress.slower(),
+        Control::FinishStanza => return progress.finish_stanza(),
+        Control::RevealAll => return progress.reveal_all(),
+    }
+    ""
+}
+
+/// A one-line summary of the reveal and the keys that apply to it.
+pub fn status_line(progress: &Progress, navigable: bool, exit_keys: &[KeyBinding]) -> String {
+    let mut line = if progress.is_complete() {
+        "■ done".to_string()
+    } else if progress.is_paused() {
+        format!("⏸ paused · {} ms/char · space resume", progress.speed_ms())
+    } else {
+        format!(
+            "▶ {} ms/char · space pause · +/- speed · → stanza · s all",
+            progress.speed_ms()
+        )
+    };
+
+    if navigable {
+        line.push_str(" · n next · p previous");
+    }
+    if let Some(exit) = exit_keys.first() {
+        line.push_str(&format!(" · {exit} quit"));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reveal::Unit;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn keys_map_to_controls() {
+        assert_eq!(
+            control(&key(KeyCode::Char(' '))),
+            Some(Control::TogglePause)
+        );
+        assert_eq!(control(&key(KeyCode::Char('+'))), Some(Control::Faster));
+        assert_eq!(control(&key(KeyCode::Char('-'))), Some(Control::Slower));
+        assert_eq!(control(&key(KeyCode::Tab)), Some(Control::FinishStanza));
+        assert_eq!(control(&key(KeyCode::Char('s'))), Some(Control::RevealAll));
+        assert_eq!(control(&key(KeyCode::Char('x'))), None);
+    }
+
+    #[test]
+    fn status_line_follows_progress() {
+        let exit = KeyBinding::default_exit_keys();
+        let mut progress = Progress::new("one two", Unit::Word, 25);
+        assert!(status_line(&progress, false, &exit).starts_with("▶ 25 ms/char"));
+
+        apply(&mut progress, Control::TogglePause);
+        assert!(status_line(&progress, false, &exit).starts_with("⏸ paused"));
+
+        assert_eq!(apply(&mut progress, Control::RevealAll), "one two");
+        let done = status_line(&progress, true, &exit);
+        assert!(done.starts_with("■ done"));
+        assert!(done.contains("n next"));
+        assert!(done.ends_with("esc quit"));
+    }
+}
@@ -0,0 +1,1534 @@
+//! The reveal engine: a writing played on the terminal, recorded or
+//! exported, with the configured pace, mode and theme.
+//!
+//! [`RevealEngine`] holds everything a reveal needs besides the text. It
+//! drives a [`Renderer`] unit by unit, answering the playback keys, and
+//! adds the title cards, section breaks and playlists around it.
+
+use std::io::{self, IsTerminal, Write};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent},
+    queue,
+    style::{self, Stylize},
+    terminal::{self, ClearType},
+};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::Rect;
+use ratatui::{TerminalOptions, Viewport};
+
+use crate::browser;
+use crate::cinema::Sequence;
+use crate::clock::{Clock, EventSource, SystemClock, TerminalEvents};
+use crate::config::{Mode, Origin, Settings};
+use crate::controls::{self, Control};
+use crate::export;
+use crate::keys::KeyBinding;
+use crate::library::{self, Library};
+use crate::playlist::{self, Playlist, Step};
+use crate::record::{self, Recording, Size};
+use crate::render::{self, Plain, Renderer, Tui, Typewriter};
+use crate::reveal::{Pauses, Unit};
+use crate::scheduler::Scheduler;
+use crate::terminal::TerminalGuard;
+use crate::theme::{self, Theme};
+use crate::viewport;
+use crate::writing::Writing;
+
+/// How long a single writing's title card stays up before the reveal.
+pub const TITLE_CARD_HOLD: Duration = Duration::from_secs(3);
+/// How long a recording or export keeps the finished writing on screen.
+pub const RECORDING_TAIL: Duration = Duration::from_secs(3);
+
+/// Everything a reveal needs besides the text.
+///
+/// # Examples
+///
+/// Unveiling a bundled poem on the terminal, as `unveilox-cli invictus` does:
+///
+/// ```no_run
+/// use unveilox::{Library, RevealEngine};
+///
+/// let writing = Library::bundled().read("invictus")?;
+/// RevealEngine::default().reveal(&writing)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// Recording it instead, which needs no terminal:
+///
+/// ```
+/// use unveilox::record::Size;
+/// use unveilox::{Library, RevealEngine};
+///
+/// let writing = Library::bundled().read("invictus")?;
+/// let recording = RevealEngine::default().record(&writing, Size::default())?;
+/// let mut cast = Vec::new();
+/// recording.write_to(&mut cast)?;
+/// assert!(String::from_utf8(cast)?.contains("Invictus"));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct RevealEngine {
+    settings: Settings,
+    theme: Theme,
+    /// The mode actually used, which is plain whenever stdout is not a terminal.
+    mode: Mode,
+    /// Whether plain output keeps the reveal's pace.
+    paced: bool,
+    /// Where a single writing's reveal is recorded to instead of played.
+    record: Option<record::Target>,
+}
+
+impl Default for RevealEngine {
+    fn default() -> Self {
+        Self::new(Settings::default(), Theme::default())
+    }
+}
+
+impl RevealEngine {
+    /// An engine playing with `settings` and `theme`. Reveals are plain when
+    /// stdout is not a terminal, and `NO_COLOR` drops the colours of any
+    /// theme not chosen on the command line.
+    pub fn new(settings: Settings, theme: Theme) -> Self {
+        let mode = if io::stdout().is_terminal() {
+            settings.mode.value
+        } else {
+            Mode::Plain
+        };
+
+        // NO_COLOR wins over configured themes, but not over an explicit --theme.
+        let theme = if theme::no_color_requested() && settings.theme.origin != Origin::CommandLine {
+            theme.without_color()
+        } else {
+            theme
+        };
+
+        Self {
+            settings,
+            theme,
+            mode,
+            paced: false,
+            record: None,
+        }
+    }
+
+    /// Keeps the reveal's pace in plain output instead of printing at once.
+    pub fn with_paced(mut self, paced: bool) -> Self {
+        self.paced = paced;
+        self
+    }
+
+    /// Makes [`reveal`](Self::reveal) record to `target` instead of playing.
+    pub fn with_record(mut self, target: record::Target) -> Self {
+        self.record = Some(target);
+        self
+    }
+
+    /// The settings reveals are played with.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// The theme reveals are drawn in, without colours under `NO_COLOR`.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// The mode reveals are played in.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Unveils a single writing, after a title card when its header has one.
+    pub fn reveal(&self, writing: &Writing) -> Result<()> {
+        if let Some(target) = &self.record {
+            return self.record(writing, target.size)?.save(&target.path);
+        }
+        if self.mode == Mode::Plain {
+            let mut stdout = io::stdout().lock();
+            if writing.has_heading() {
+                writeln!(stdout, "{}", render::plain_heading(writing))?;
+            }
+            return self.plain(&mut stdout, writing);
+        }
+
+        let mut guard = TerminalGuard::enter(true)?;
+        self.reveal_on(
+            &mut Screen::terminal(),
+            writing,
+            &SystemClock::new(),
+            &mut TerminalEvents,
+        )?;
+        guard.finish()
+    }
+
+    /// Streams `writing` as bare text, each section heading on a line of its
+    /// own. Paced, it comes out on the same time as on the terminal;
+    /// otherwise all at once. A closed pipe (e.g. `| head`) ends the output
+    /// quietly.
+    fn plain(&self, out: &mut impl Write, writing: &Writing) -> Result<()> {
+        let playback = Playback::new(self);
+        let result = (|| -> Result<()> {
+            for section in writing.sections() {
+                if let Some(heading) = &section.title {
+                    writeln!(out, "{heading}\n")?;
+                }
+                let mut plain = Plain::new(&mut *out);
+                if self.paced {
+                    drive_offline(&section, &playback, &mut plain, thread::sleep)?;
+                } else {
+                    drive_offline(&section, &playback, &mut plain, |_| {})?;
+                }
+                if !section.body.ends_with('\n') {
+                    writeln!(out)?;
+                }
+            }
+            out.flush()?;
+            Ok(())
+        })();
+
+        match result {
+            Err(err)
+                if err
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+            {
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
+    /// The title card and reveal of [`reveal`](Self::reveal) on a screen that
+    /// is already set up.
+    fn reveal_on(
+        &self,
+        screen: &mut Screen<impl Write>,
+        writing: &Writing,
+        clock: &impl Clock,
+        events: &mut impl EventSource,
+    ) -> Result<()> {
+        let playback = Playback::new(self);
+        let tui = self.mode == Mode::Tui;
+        let step = if writing.has_heading() {
+            // n or Enter on the card starts the reveal early.
+            let card = Playback {
+                navigable: true,
+                hold: Some(TITLE_CARD_HOLD),
+                ..playback
+            };
+            if tui {
+                let opening = Sequence::opening(writing, &self.theme, TITLE_CARD_HOLD);
+                play_sequence(screen, &opening, &card, clock, events)?
+            } else {
+                title_card(screen, writing, None, &card, clock, events)?
+            }
+        } else {
+            None
+        };
+        if step == Some(Step::Stop) {
+            return Ok(());
+        }
+
+        // With credits to roll, the finished writing only stays up a while.
+        let credits = tui && self.settings.credits.value;
+        let playback = Playback {
+            hold: credits.then_some(TITLE_CARD_HOLD).or(playback.hold),
+            ..playback
+        };
+        let step = sections(screen, writing, &playback, self.mode, clock, events)?;
+        if credits && step.is_none() {
+            let credits = Sequence::credits(writing, &self.theme);
+            play_sequence(screen, &credits, &playback, clock, events)?;
+        }
+        Ok(())
+    }
+
+    /// Plays `writing` through `renderer` while answering the playback keys,
+    /// then holds it until a key is pressed. The renderer's screen must
+    /// already be set up: raw mode, on the alternate screen.
+    pub fn play(&self, writing: &Writing, renderer: &mut impl Renderer) -> Result<Option<Step>> {
+        self.play_with(writing, renderer, &SystemClock::new(), &mut TerminalEvents)
+    }
+
+    /// [`play`](Self::play) on the time of `clock`, answering `events`
+    /// instead of the terminal's. With a [`VirtualClock`](crate::clock::VirtualClock)
+    /// and [`ScriptedEvents`](crate::clock::ScriptedEvents) a reveal plays in
+    /// an instant, the same every time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    /// use unveilox::clock::{Clock, ScriptedEvents, VirtualClock};
+    /// use unveilox::render::Memory;
+    /// use unveilox::{RevealEngine, Step, Writing};
+    ///
+    /// let writing = Writing::parse("note", "Hello\n")?;
+    /// let clock = VirtualClock::new();
+    /// // Esc, a minute in.
+    /// let esc = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    /// let mut events = ScriptedEvents::new(&clock, [(Duration::from_secs(60), esc)]);
+    /// let mut memory = Memory::with_size((20, 5));
+    /// let engine = RevealEngine::default();
+    /// let step = engine.play_with(&writing, &mut memory, &clock, &mut events)?;
+    ///
+    /// assert_eq!(step, Some(Step::Stop));
+    /// assert_eq!(memory.lines, ["Hello"]);
+    /// assert_eq!(clock.now(), Duration::from_secs(60));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn play_with(
+        &self,
+        writing: &Writing,
+        renderer: &mut impl Renderer,
+        clock: &impl Clock,
+        events: &mut impl EventSource,
+    ) -> Result<Option<Step>> {
+        drive(writing, &Playback::new(self), renderer, clock, events)
+    }
+
+    /// Plays `writing` through `renderer` without waiting or reading keys.
+    /// `wait` is called after each frame with how long it would stay up.
+    pub fn play_offline(
+        &self,
+        writing: &Writing,
+        renderer: &mut impl Renderer,
+        wait: impl FnMut(Duration),
+    ) -> Result<()> {
+        drive_offline(writing, &Playback::new(self), renderer, wait)
+    }
+
+    /// The reveal [`reveal`](Self::reveal) would play, title and section
+    /// cards included, recorded on a screen of `size` as if no key were
+    /// pressed. The configured mode is used even when stdout is not a
+    /// terminal.
+    pub fn record(&self, writing: &Writing, size: Size) -> Result<Recording> {
+        let mode = self.settings.mode.value;
+        let theme = &self.theme;
+        let playback = Playback::new(self);
+        let mut recording = Recording::new(size, writing.title());
+        let mut tape = recording.tape();
+
+        let mut card = |recording: &mut Recording, writing: &Writing| -> Result<()> {
+            if mode == Mode::Plain {
+                write!(
+                    tape,
+                    "{}\r\n",
+                    render::plain_heading(writing).replace('\n', "\r\n")
+                )?;
+            } else {
+                render::paint_background(&mut tape, theme)?;
+                queue!(tape, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+                render::paint_title_card(
+                    &mut tape,
+                    (size.width, size.height),
+                    writing,
+                    None,
+                    theme,
+                )?;
+            }
+            recording.frame();
+            recording.wait(TITLE_CARD_HOLD);
+            Ok(())
+        };
+        if writing.has_heading() {
+            if mode == Mode::Tui {
+                let opening = Sequence::opening(writing, theme, TITLE_CARD_HOLD);
+                record_sequence(&mut recording, &opening, theme)?;
+            } else {
+                card(&mut recording, writing)?;
+            }
+        }
+
+        let sections = writing.sections();
+        for (index, section) in sections.iter().enumerate() {
+            if section.has_heading() {
+                card(&mut recording, section)?;
+            }
+            if !section.body.trim().is_empty() {
+                record_section(&mut recording, writing, section, &playback, mode)?;
+            }
+            let last = index + 1 == sections.len();
+            recording.wait(if last {
+                RECORDING_TAIL
+            } else {
+                TITLE_CARD_HOLD
+            });
+        }
+        if mode == Mode::Tui && self.settings.credits.value {
+            record_sequence(&mut recording, &Sequence::credits(writing, theme), theme)?;
+        }
+
+        if mode != Mode::Plain {
+            queue!(tape, style::ResetColor, cursor::Show)?;
+        }
+        recording.finish();
+        Ok(recording)
+    }
+
+    /// The reveal of `writing` as an animated SVG or GIF, timed as it would
+    /// be played.
+    pub fn export(&self, writing: &Writing, options: &export::Options) -> Result<Vec<u8>> {
+        let settings = &self.settings;
+        let timing = export::Timing {
+            unit: settings.unit.value,
+            speed_ms: settings.speed.value,
+            pauses: settings.pauses.value,
+            card: TITLE_CARD_HOLD,
+            tail: RECORDING_TAIL,
+        };
+        export::export(writing, &self.theme, &timing, options)
+    }
+
+    /// Loads the writing called `name`, letting the user choose when it is
+    /// the prefix of several and there is a terminal to ask on.
+    /// `None` means the choice was cancelled.
+    pub fn read(&self, library: &Library, name: &str) -> Result<Option<Writing>> {
+        let err = match library.read_fuzzy(name) {
+            Ok(writing) => return Ok(Some(writing)),
+            Err(err) => err,
+        };
+        let interactive = self.mode != Mode::Plain && io::stdin().is_terminal();
+        match err.downcast::<library::Ambiguous>() {
+            Ok(ambiguous) if interactive => {
+                let mut guard = TerminalGuard::enter(true)?;
+                let chosen = pick(
+                    &mut Screen::terminal(),
+                    &ambiguous,
+                    &Playback::new(self),
+                    &mut TerminalEvents,
+                )?;
+                guard.finish()?;
+                match chosen {
+                    Some(chosen) => library.read(&chosen).map(Some),
+                    None => Ok(None),
+                }
+            }
+            Ok(ambiguous) => Err(anyhow::Error::new(ambiguous)),
+            Err(err) => Err(err),
+        }
+        .with_context(|| format!("while reading '{name}'"))
+    }
+
+    /// The library browser. Each writing chosen is unveiled, and the browser
+    /// comes back when it is done. Returns the writings left out because
+    /// they failed to load.
+    pub fn browse(&self, library: &Library) -> Result<Vec<library::Skipped>> {
+        let (browser, skipped) = browser::Browser::load(library, &self.settings)?;
+        let mut guard = TerminalGuard::enter(true)?;
+        self.browse_on(
+            &mut Screen::terminal(),
+            browser,
+            &SystemClock::new(),
+            &mut TerminalEvents,
+        )?;
+        guard.finish()?;
+        Ok(skipped)
+    }
+
+    /// The loop of [`browse`](Self::browse) on a screen that is already set
+    /// up.
+    fn browse_on(
+        &self,
+        screen: &mut Screen<impl Write>,
+        mut browser: browser::Browser,
+        clock: &impl Clock,
+        events: &mut impl EventSource,
+    ) -> Result<()> {
+        let playback = Playback::new(self);
+
+        loop {
+            screen.blank(playback.theme)?;
+            let choice = {
+                let mut terminal = screen.ratatui()?;
+                terminal.hide_cursor()?;
+                loop {
+                    terminal.draw(|f| browser.draw(f, playback.theme, playback.exit_keys))?;
+                    if let Event::Key(key) = next_event(events)? {
+                        if let Some(choice) = browser.handle(&key, playback.exit_keys) {
+                            break choice;
+                        }
+                    }
+                }
+            };
+
+            match (choice, browser.selected()) {
+                (browser::Choice::Play, Some(item)) => {
+                    self.reveal_on(screen, &item.writing, clock, events)?
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Plays the writings of `playlist` one after another, each after a card
+    /// and held for `delay`.
+    pub fn play_playlist(
+        &self,
+        library: &Library,
+        playlist: &Playlist,
+        delay: Duration,
+    ) -> Result<()> {
+        // Load everything up front so a bad entry fails before the screen changes.
+        let writings = playlist
+            .entries()
+            .iter()
+            .map(|name| {
+                library
+                    .read(name)
+                    .with_context(|| format!("while reading '{name}'"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let total = writings.len();
+
+        if self.mode == Mode::Plain {
+            let mut stdout = io::stdout().lock();
+            for (current, writing) in writings.iter().enumerate() {
+                if current > 0 {
+                    writeln!(stdout)?;
+                    if self.paced {
+                        thread::sleep(delay);
+                    }
+                }
+                writeln!(
+                    stdout,
+                    "== {} ({} / {total}) ==",
+                    writing.title(),
+                    current + 1
+                )?;
+                if let Some(byline) = writing.byline() {
+                    writeln!(stdout, "By {byline}")?;
+                }
+                writeln!(stdout)?;
+                self.plain(&mut stdout, writing)?;
+            }
+            return Ok(());
+        }
+
+        let mut guard = TerminalGuard::enter(true)?;
+        self.play_writings(
+            &mut Screen::terminal(),
+            &writings,
+            delay,
+            &SystemClock::new(),
+            &mut TerminalEvents,
+        )?;
+        guard.finish()
+    }
+
+    /// The writings of a playlist on a screen that is already set up, each
+    /// after a card and held for `delay`.
+    fn play_writings(
+        &self,
+        screen: &mut Screen<impl Write>,
+        writings: &[Writing],
+        delay: Duration,
+        clock: &impl Clock,
+        events: &mut impl EventSource,
+    ) -> Result<()> {
+        let total = writings.len();
+        let mut index = Some(0);
+
+        while let Some(current) = index {
+            let writing = &writings[current];
+            let last = current + 1 == total;
+            let card = Playback {
+                navigable: true,
+                hold: Some(delay),
+                ..Playback::new(self)
+            };
+            let playback = Playback {
+                hold: (!last).then_some(delay),
+                ..card
+            };
+
+            // Skipping forward on the card just starts the writing early.
+            let counter = format!("{} / {total}", current + 1);
+            let step = match title_card(screen, writing, Some(&counter), &card, clock, events)? {
+                Some(Step::Next) | None => {
+                    sections(screen, writing, &playback, self.mode, clock, events)?
+                }
+                Some(step) => Some(step),
+            };
+
+            index = playlist::advance(current, total, step.unwrap_or(Step::Next));
+        }
+        Ok(())
+    }
+}
+
+/// How a single writing is played back.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Playback<'a> {
+    pub(crate) speed_ms: u64,
+    pub(crate) unit: Unit,
+    pub(crate) pauses: Pauses,
+    /// Whether n/p move between queued writings.
+    pub(crate) navigable: bool,
+    /// How long a finished reveal stays on screen; `None` waits for a key.
+    pub(crate) hold: Option<Duration>,
+    pub(crate) exit_keys: &'a [KeyBinding],
+    pub(crate) theme: &'a Theme,
+}
+
+impl<'a> Playback<'a> {
+    /// A single writing played as `engine` is set up to.
+    pub(crate) fn new(engine: &'a RevealEngine) -> Self {
+        Self {
+            speed_ms: engine.settings.speed.value,
+            unit: engine.settings.unit.value,
+            pauses: engine.settings.pauses.value,
+            navigable: false,
+            hold: None,
+            exit_keys: &engine.settings.exit_keys.value,
+            theme: &engine.theme,
+        }
+    }
+}
+
+/// Where the live reveals, cards and sequences are drawn: the terminal, or
+/// any output of a set size.
+struct Screen<W> {
+    out: W,
+    /// The size in cells, or `None` to follow the terminal's.
+    size: Option<(u16, u16)>,
+}
+
+impl Screen<io::Stdout> {
+    fn terminal() -> Self {
+        Self {
+            out: io::stdout(),
+            size: None,
+        }
+    }
+}
+
+impl<W: Write> Screen<W> {
+    fn size(&self) -> Result<(u16, u16)> {
+        match self.size {
+            Some(size) => Ok(size),
+            None => Ok(terminal::size()?),
+        }
+    }
+
+    /// Paints the theme's background over the whole screen and moves the
+    /// cursor home.
+    fn blank(&mut self, theme: &Theme) -> Result<()> {
+        render::paint_background(&mut self.out, theme)?;
+        queue!(
+            self.out,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        Ok(())
+    }
+
+    /// A ratatui terminal over the screen, for the full-screen views.
+    fn ratatui(&mut self) -> Result<ratatui::Terminal<CrosstermBackend<&mut W>>> {
+        let backend = CrosstermBackend::new(&mut self.out);
+        let terminal = match self.size {
+            Some((width, height)) => {
+                let options = TerminalOptions {
+                    viewport: Viewport::Fixed(Rect::new(0, 0, width, height)),
+                };
+                ratatui::Terminal::with_options(backend, options)?
+            }
+            None => ratatui::Terminal::new(backend)?,
+        };
+        Ok(terminal)
+    }
+
+    fn typewriter<'a>(&mut self, theme: &'a Theme) -> Typewriter<'a, &mut W> {
+        let typewriter = Typewriter::new(&mut self.out, theme);
+        match self.size {
+            Some(size) => typewriter.with_size(size),
+            None => typewriter,
+        }
+    }
+
+    fn tui<'a>(&mut self, theme: &'a Theme) -> Result<Tui<'a, CrosstermBackend<&mut W>>> {
+        match self.size {
+            Some(size) => Tui::with_size(&mut self.out, size, theme),
+            None => Tui::new(&mut self.out, theme),
+        }
+    }
+}
+
+/// Shows a title sequence or the credits on `screen`.
+fn play_sequence(
+    screen: &mut Screen<impl Write>,
+    sequence: &Sequence,
+    playback: &Playback,
+    clock: &impl Clock,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    run_sequence(
+        &mut screen.tui(playback.theme)?,
+        sequence,
+        playback,
+        clock,
+        events,
+    )
+}
+
+/// Shows each frame of `sequence` for its time. A key skips the rest, and
+/// exit and navigation keys are answered as during a reveal.
+fn run_sequence(
+    tui: &mut Tui<impl Backend>,
+    sequence: &Sequence,
+    playback: &Playback,
+    clock: &impl Clock,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    tui.begin()?;
+    let mut due = clock.now();
+    for (frame, hold) in sequence
+        .frames(tui.screen()?.height)
+        .into_iter()
+        .enumerate()
+    {
+        tui.show(sequence, frame)?;
+        due += hold;
+        loop {
+            let left = due.saturating_sub(clock.now());
+            if left.is_zero() {
+                break;
+            }
+            if let Some(Event::Key(key)) = events.poll(left)? {
+                return Ok(key_step(&key, playback));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Plays `writing` unit by unit while handling the playback controls, then
+/// holds it on screen and lets it be scrolled. The [`Scheduler`] draws
+/// whenever the progress or the rows in view have changed. Time comes from
+/// `clock` and keys from `events`.
+fn drive(
+    writing: &Writing,
+    playback: &Playback,
+    renderer: &mut impl Renderer,
+    clock: &impl Clock,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    let tick = Duration::from_millis(100);
+    let mut scheduler = Scheduler::new(writing, playback);
+    let mut next_due = clock.now();
+    // What was left of the wait when the reveal was paused.
+    let mut paused_with = Duration::ZERO;
+    let mut finished_at: Option<Duration> = None;
+
+    renderer.begin()?;
+    scheduler.draw(renderer)?;
+
+    let step = loop {
+        let now = clock.now();
+        let progress = scheduler.progress();
+        if !progress.is_paused() && now >= next_due {
+            if let Some(pause) = scheduler.advance() {
+                next_due = now + pause;
+                scheduler.draw(renderer)?;
+            }
+        }
+
+        let progress = scheduler.progress();
+        let timeout = if progress.is_complete() {
+            // After the full reveal, wait for a key or until the hold runs out
+            let finished = *finished_at.get_or_insert(now);
+            match playback.hold {
+                Some(hold) => {
+                    let left = (finished + hold).saturating_sub(now);
+                    if left.is_zero() {
+                        break None;
+                    }
+                    left.min(tick)
+                }
+                None => tick,
+            }
+        } else if progress.is_paused() {
+            tick
+        } else {
+            next_due.saturating_sub(clock.now())
+        };
+
+        let Some(event) = events.poll(timeout)? else {
+            continue;
+        };
+        if let Event::Key(key) = &event {
+            if let Some(step) = key_step(key, playback) {
+                break Some(step);
+            }
+            if let Some(control) = controls::control(key) {
+                let now = clock.now();
+                let left = next_due.saturating_sub(now);
+                let speed = scheduler.progress().speed_ms();
+                scheduler.control(control);
+                match control {
+                    // The wait under way runs on at the new pace
+                    Control::Faster | Control::Slower => {
+                        let ratio = scheduler.progress().speed_ms() as f64 / speed as f64;
+                        if scheduler.progress().is_paused() {
+                            paused_with = paused_with.mul_f64(ratio);
+                        } else {
+                            next_due = now + left.mul_f64(ratio);
+                        }
+                    }
+                    Control::TogglePause if scheduler.progress().is_paused() => paused_with = left,
+                    Control::TogglePause => next_due = now + paused_with,
+                    _ => {}
+                }
+                scheduler.draw(renderer)?;
+                continue;
+            }
+        }
+        // Scrolling by hand waits until the reveal is over
+        match viewport::scroll_for(&event) {
+            Some(scroll) if scheduler.progress().is_complete() => {
+                scheduler.scroll(scroll);
+                scheduler.draw(renderer)?;
+            }
+            _ if matches!(event, Event::Resize(..)) => scheduler.draw(renderer)?,
+            _ => {}
+        }
+    };
+    renderer.finish()?;
+    Ok(step)
+}
+
+/// Draws `writing` with one of the terminal renderers; plain output never
+/// gets here because it does not take over the screen.
+///
+/// Each section of a Markdown writing opens with a title card for its
+/// heading. Sections before the last move on by themselves, and n/p step
+/// between them.
+fn sections(
+    screen: &mut Screen<impl Write>,
+    writing: &Writing,
+    playback: &Playback,
+    mode: Mode,
+    clock: &impl Clock,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    let sections = writing.sections();
+    let total = sections.len();
+    let between = Playback {
+        navigable: true,
+        hold: Some(TITLE_CARD_HOLD),
+        ..*playback
+    };
+    let mut index = Some(0);
+    let mut step = None;
+
+    while let Some(current) = index {
+        let section = &sections[current];
+        let last = current + 1 == total;
+        step = if section.has_heading() {
+            title_card(screen, section, None, &between, clock, events)?
+        } else {
+            None
+        };
+        if matches!(step, None | Some(Step::Next)) && !section.body.trim().is_empty() {
+            let playback = if last { playback } else { &between };
+            step = match mode {
+                Mode::Tui => {
+                    let mut tui = screen.tui(playback.theme)?.titled(writing.title());
+                    drive(section, playback, &mut tui, clock, events)?
+                }
+                Mode::Typewriter | Mode::Plain => {
+                    let mut typewriter = screen.typewriter(playback.theme);
+                    drive(section, playback, &mut typewriter, clock, events)?
+                }
+            };
+        }
+        if last {
+            break;
+        }
+        index = playlist::advance(current, total, step.unwrap_or(Step::Next));
+    }
+    Ok(step)
+}
+
+/// One section of [`RevealEngine::record`], drawn by the renderer for `mode`
+/// under the title of the whole `writing`.
+fn record_section(
+    recording: &mut Recording,
+    writing: &Writing,
+    section: &Writing,
+    playback: &Playback,
+    mode: Mode,
+) -> Result<()> {
+    let size = recording.size();
+    let size = (size.width, size.height);
+    let tape = recording.tape();
+    let wait = |pause| {
+        recording.frame();
+        recording.wait(pause);
+    };
+    match mode {
+        Mode::Tui => {
+            let mut tui = Tui::with_size(tape, size, playback.theme)?.titled(writing.title());
+            drive_offline(section, playback, &mut tui, wait)
+        }
+        Mode::Typewriter => {
+            let mut typewriter = Typewriter::new(tape, playback.theme).with_size(size);
+            drive_offline(section, playback, &mut typewriter, wait)
+        }
+        Mode::Plain => drive_offline(section, playback, &mut Plain::raw(tape), wait),
+    }
+}
+
+/// A title sequence or the credits in a recording, as
+/// [`run_sequence`] shows them when no key is pressed.
+fn record_sequence(recording: &mut Recording, sequence: &Sequence, theme: &Theme) -> Result<()> {
+    let size = recording.size();
+    let mut tui = Tui::with_size(recording.tape(), (size.width, size.height), theme)?;
+    tui.begin()?;
+    for (frame, hold) in sequence.frames(size.height).into_iter().enumerate() {
+        tui.show(sequence, frame)?;
+        recording.frame();
+        recording.wait(hold);
+    }
+    Ok(())
+}
+
+/// [`drive`] without a clock or keys: every step is drawn, then `wait` is
+/// told how long it stays up.
+fn drive_offline(
+    writing: &Writing,
+    playback: &Playback,
+    renderer: &mut impl Renderer,
+    mut wait: impl FnMut(Duration),
+) -> Result<()> {
+    let mut scheduler = Scheduler::new(writing, playback);
+
+    renderer.begin()?;
+    scheduler.draw(renderer)?;
+    wait(Duration::ZERO);
+    while let Some(pause) = scheduler.advance() {
+        scheduler.draw(renderer)?;
+        wait(pause);
+    }
+    renderer.finish()
+}
+
+/// Shows the writing's title and byline, plus its place in a playlist when
+/// there is a `counter`.
+fn title_card(
+    screen: &mut Screen<impl Write>,
+    writing: &Writing,
+    counter: Option<&str>,
+    playback: &Playback,
+    clock: &impl Clock,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    let size = screen.size()?;
+    screen.blank(playback.theme)?;
+    render::paint_title_card(&mut screen.out, size, writing, counter, playback.theme)?;
+    hold_screen(playback.hold, playback, clock, events)
+}
+
+/// Full-screen list of the writings an ambiguous name could mean. Up/down
+/// or a digit choose, Enter confirms, and Esc or an exit key cancels.
+fn pick(
+    screen: &mut Screen<impl Write>,
+    ambiguous: &library::Ambiguous,
+    playback: &Playback,
+    events: &mut impl EventSource,
+) -> Result<Option<String>> {
+    let candidates = &ambiguous.candidates;
+    let style = theme::content_style(playback.theme.base_style());
+    let mut selected: usize = 0;
+
+    loop {
+        screen.blank(playback.theme)?;
+        let (width, height) = screen.size()?;
+        let heading = format!("`{}` could mean:", ambiguous.query);
+        let footer = "↑/↓ choose · enter play · esc cancel";
+        let visible = usize::from(height.saturating_sub(4)).max(1);
+        let first = (selected + 1).saturating_sub(visible);
+
+        let stdout = &mut screen.out;
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 0),
+            style::PrintStyledContent(style.apply(heading.as_str()).bold()),
+        )?;
+        for (row, (index, candidate)) in candidates
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .enumerate()
+        {
+            let line: String = format!("{:>2}. {candidate}", index + 1)
+                .chars()
+                .take(usize::from(width.saturating_sub(3)))
+                .collect();
+            let y = u16::try_from(row + 2).unwrap_or(u16::MAX);
+            queue!(stdout, cursor::MoveTo(0, y))?;
+            if index == selected {
+                queue!(
+                    stdout,
+                    style::PrintStyledContent(style.apply(format!("> {line}")).reverse())
+                )?;
+            } else {
+                queue!(
+                    stdout,
+                    style::PrintStyledContent(style.apply(format!("  {line}")))
+                )?;
+            }
+        }
+        queue!(
+            stdout,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            style::PrintStyledContent(footer.dark_grey()),
+        )?;
+        stdout.flush()?;
+
+        let Event::Key(key) = next_event(events)? else {
+            continue;
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(candidates.len() - 1)
+            }
+            KeyCode::Enter => break,
+            KeyCode::Char(digit @ '1'..='9') => {
+                let index = digit as usize - '1' as usize;
+                if index < candidates.len() {
+                    selected = index;
+                    break;
+                }
+            }
+            KeyCode::Esc => return Ok(None),
+            _ if is_exit_key(&key, playback.exit_keys) => return Ok(None),
+            _ => {}
+        }
+    }
+
+    Ok(Some(candidates[selected].name.clone()))
+}
+
+fn is_exit_key(key: &KeyEvent, exit_keys: &[KeyBinding]) -> bool {
+    exit_keys.iter().any(|binding| binding.matches(key))
+}
+
+/// Keys that move between writings while a playlist is playing.
+fn navigation_step(key: &KeyEvent) -> Option<Step> {
+    match key.code {
+        KeyCode::Enter | KeyCode::Char('n') => Some(Step::Next),
+        KeyCode::Char('p') => Some(Step::Previous),
+        _ => None,
+    }
+}
+
+fn key_step(key: &KeyEvent, playback: &Playback) -> Option<Step> {
+    if playback.navigable {
+        if let Some(step) = navigation_step(key) {
+            return Some(step);
+        }
+    }
+
+    is_exit_key(key, playback.exit_keys).then_some(Step::Stop)
+}
+
+fn poll_step(
+    timeout: Duration,
+    playback: &Playback,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    match events.poll(timeout)? {
+        Some(Event::Key(key)) => Ok(key_step(&key, playback)),
+        _ => Ok(None),
+    }
+}
+
+/// Waits as long as it takes for the next event.
+fn next_event(events: &mut impl EventSource) -> Result<Event> {
+    loop {
+        if let Some(event) = events.poll(Duration::from_millis(100))? {
+            return Ok(event);
+        }
+    }
+}
+
+/// Keeps the current screen up until a key is pressed or `hold` runs out.
+fn hold_screen(
+    hold: Option<Duration>,
+    playback: &Playback,
+    clock: &impl Clock,
+    events: &mut impl EventSource,
+) -> Result<Option<Step>> {
+    let deadline = hold.map(|hold| clock.now() + hold);
+    let tick = Duration::from_millis(100);
+
+    loop {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let left = deadline.saturating_sub(clock.now());
+                if left.is_zero() {
+                    return Ok(None);
+                }
+                left.min(tick)
+            }
+            None => tick,
+        };
+
+        if let Some(step) = poll_step(timeout, playback, events)? {
+            return Ok(Some(step));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cinema;
+    use crate::clock::{ScriptedEvents, VirtualClock};
+    use crate::render::Memory;
+    use crate::writing::Syntax;
+    use crossterm::event::KeyModifiers;
+    use ratatui::backend::TestBackend;
+
+    fn test_engine(settings: Settings) -> RevealEngine {
+        RevealEngine {
+            mode: settings.mode.value,
+            settings,
+            theme: Theme::default(),
+            paced: false,
+            record: None,
+        }
+    }
+
+    #[test]
+    fn recordings_need_no_terminal_and_repeat_exactly() {
+        let dir = tempfile::tempdir().unwrap();
+        let writing = Writing::parse(
+            "night",
+            "---\ntitle: Night\n---\nOut of the night,\nblack\n",
+        )
+        .unwrap();
+        for mode in [Mode::Typewriter, Mode::Tui, Mode::Plain] {
+            let mut settings = Settings::default();
+            settings.mode.value = mode;
+            // Only the TUI rolls credits.
+            settings.credits.value = true;
+            let target = record::Target {
+                path: dir.path().join(format!("{mode}.cast")),
+                size: "40x10".parse().unwrap(),
+            };
+            let engine = test_engine(settings).with_record(target.clone());
+            engine.reveal(&writing).unwrap();
+            let cast = std::fs::read_to_string(&target.path).unwrap();
+            engine.reveal(&writing).unwrap();
+            assert_eq!(cast, std::fs::read_to_string(&target.path).unwrap());
+
+            let events: Vec<serde_json::Value> = cast
+                .lines()
+                .skip(1)
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            assert!(events[0][2].as_str().unwrap().contains("Night"));
+            let (opening, credits) = if mode == Mode::Tui {
+                let credits = Sequence::credits(&writing, engine.theme()).frames(10);
+                (cinema::FADE_IN + TITLE_CARD_HOLD, credits.iter().sum())
+            } else {
+                assert_eq!(events[1][0], TITLE_CARD_HOLD.as_secs_f64());
+                (TITLE_CARD_HOLD, Duration::ZERO)
+            };
+
+            let playback = Playback::new(&engine);
+            let reveal = Scheduler::new(&writing, &playback).progress().duration();
+            let end = (opening + reveal + RECORDING_TAIL + credits).as_secs_f64();
+            let last = events.last().unwrap()[0].as_f64().unwrap();
+            assert!((last - end).abs() < 1e-6, "{mode}: {last} != {end}");
+        }
+    }
+
+    #[test]
+    fn plain_output_is_the_bare_text_under_each_heading() {
+        let engine = test_engine(Settings::default());
+        let writing = Writing::parse_as(
+            "ode",
+            "# Ode\n\nFirst {speed:90}*stanza*{/speed}\n\n## Turn\n\nSecond",
+            Syntax::Markdown,
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        engine.plain(&mut out, &writing).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "First stanza\n\nTurn\n\nSecond\n"
+        );
+    }
+
+    #[test]
+    fn n_skips_the_rest_of_a_section_card() {
+        let engine = test_engine(Settings::default());
+        let writing = Writing::parse_as("ode", "# Ode\n\n## Turn\n\nab", Syntax::Markdown).unwrap();
+        let playback = Playback {
+            hold: Some(Duration::from_secs(1)),
+            ..Playback::new(&engine)
+        };
+        let play = |script: Vec<(Duration, Event)>| {
+            let clock = VirtualClock::new();
+            let mut events = ScriptedEvents::new(&clock, script);
+            let mut screen = Screen {
+                out: Vec::new(),
+                size: Some((20, 6)),
+            };
+            let step = sections(
+                &mut screen,
+                &writing,
+                &playback,
+                Mode::Typewriter,
+                &clock,
+                &mut events,
+            )
+            .unwrap();
+            assert_eq!(step, None);
+            let shown = String::from_utf8(screen.out).unwrap();
+            assert!(shown.contains("Turn") && shown.contains('b'));
+            clock.now()
+        };
+
+        let next = Event::Key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE));
+        let skipped = play(vec![(Duration::from_millis(500), next)]);
+        let held = play(Vec::new());
+        assert_eq!(held - skipped, TITLE_CARD_HOLD - Duration::from_millis(500));
+    }
+
+    fn keys(script: &[(u64, KeyCode)]) -> Vec<(Duration, Event)> {
+        script
+            .iter()
+            .map(|&(ms, code)| {
+                let key = KeyEvent::new(code, KeyModifiers::NONE);
+                (Duration::from_millis(ms), Event::Key(key))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn picker_chooses_with_keys_and_cancels_with_esc() {
+        let engine = test_engine(Settings::default());
+        let playback = Playback::new(&engine);
+        let ambiguous = library::Ambiguous {
+            query: "inv".to_string(),
+            candidates: ["invictus", "invocation"]
+                .map(|name| library::Candidate {
+                    name: name.to_string(),
+                    title: None,
+                })
+                .to_vec(),
+        };
+        let choose = |script: &[(u64, KeyCode)]| {
+            let clock = VirtualClock::new();
+            let mut events = ScriptedEvents::new(&clock, keys(script));
+            let mut screen = Screen {
+                out: Vec::new(),
+                size: Some((30, 6)),
+            };
+            let chosen = pick(&mut screen, &ambiguous, &playback, &mut events).unwrap();
+            let shown = String::from_utf8(screen.out).unwrap();
+            assert!(shown.contains("`inv` could mean:") && shown.contains("2. invocation"));
+            chosen
+        };
+
+        let down = choose(&[(0, KeyCode::Down), (1, KeyCode::Enter)]);
+        assert_eq!(down.as_deref(), Some("invocation"));
+        assert_eq!(
+            choose(&[(0, KeyCode::Char('1'))]).as_deref(),
+            Some("invictus")
+        );
+        assert_eq!(choose(&[(0, KeyCode::Down), (1, KeyCode::Esc)]), None);
+    }
+
+    #[test]
+    fn browser_unveils_the_chosen_writing_and_comes_back() {
+        let engine = test_engine(Settings::default());
+        let (browser, skipped) =
+            browser::Browser::load(&Library::bundled(), &engine.settings).unwrap();
+        assert!(skipped.is_empty());
+        let clock = VirtualClock::new();
+        // Enter plays the first writing; after its card, s shows all of it,
+        // q ends it and q again leaves the browser.
+        let script = [
+            (0, KeyCode::Enter),
+            (5_000, KeyCode::Char('s')),
+            (6_000, KeyCode::Char('q')),
+            (7_000, KeyCode::Char('q')),
+        ];
+        let mut events = ScriptedEvents::new(&clock, keys(&script));
+        let mut screen = Screen {
+            out: Vec::new(),
+            size: Some((60, 20)),
+        };
+
+        engine
+            .browse_on(&mut screen, browser, &clock, &mut events)
+            .unwrap();
+        assert_eq!(clock.now(), Duration::from_secs(7));
+        let shown = String::from_utf8(screen.out).unwrap();
+        let (before, after) = shown.split_once("■ done").unwrap();
+        assert!(before.contains("Rudyard Kipling, 1910"));
+        assert!(before.contains("/ filter · esc quit"));
+        assert!(after.contains("/ filter · esc quit"));
+    }
+
+    #[test]
+    fn playlist_plays_each_writing_until_an_exit_key() {
+        let engine = test_engine(Settings::default());
+        let writings = [
+            Writing::parse("one", "---\ntitle: First\n---\nab\n").unwrap(),
+            Writing::parse("two", "---\ntitle: Second\n---\ncd\n").unwrap(),
+        ];
+        let play = |script: &[(u64, KeyCode)]| {
+            let clock = VirtualClock::new();
+            let mut events = ScriptedEvents::new(&clock, keys(script));
+            let mut screen = Screen {
+                out: Vec::new(),
+                size: Some((30, 8)),
+            };
+            engine
+                .play_writings(
+                    &mut screen,
+                    &writings,
+                    Duration::from_secs(1),
+                    &clock,
+                    &mut events,
+                )
+                .unwrap();
+            String::from_utf8(screen.out).unwrap()
+        };
+
+        let whole = play(&[(60_000, KeyCode::Char('q'))]);
+        assert!(whole.contains("First") && whole.contains("1 / 2"));
+        assert!(whole.contains("Second") && whole.contains("2 / 2"));
+        let stopped = play(&[(500, KeyCode::Char('q'))]);
+        assert!(stopped.contains("First") && !stopped.contains("Second"));
+    }
+
+    #[test]
+    fn playlist_keys_only_navigate_in_playlists() {
+        let engine = test_engine(Settings::default());
+        let single = Playback::new(&engine);
+        let queued = Playback {
+            navigable: true,
+            ..single
+        };
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            key_step(&key(KeyCode::Char('n')), &queued),
+            Some(Step::Next)
+        );
+        assert_eq!(
+            key_step(&key(KeyCode::Char('p')), &queued),
+            Some(Step::Previous)
+        );
+        assert_eq!(key_step(&key(KeyCode::Enter), &queued), Some(Step::Next));
+        assert_eq!(key_step(&key(KeyCode::Enter), &single), Some(Step::Stop));
+        assert_eq!(key_step(&key(KeyCode::Char('n')), &single), None);
+        assert_eq!(
+            key_step(&key(KeyCode::Char('q')), &queued),
+            Some(Step::Stop)
+        );
+    }
+
+    #[test]
+    fn config_exit_keys_replace_defaults() {
+        let mut settings = Settings::default();
+        settings.exit_keys.value = vec!["x".parse().unwrap()];
+        let engine = test_engine(settings);
+        let playback = Playback::new(&engine);
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            key_step(&key(KeyCode::Char('x')), &playback),
+            Some(Step::Stop)
+        );
+        assert_eq!(key_step(&key(KeyCode::Char('q')), &playback), None);
+    }
+
+    #[test]
+    fn a_virtual_clock_plays_the_exact_frame_sequence() {
+        let mut settings = Settings::default();
+        settings.unit.value = Unit::Char;
+        settings.speed.value = 10;
+        settings.pauses.value = Pauses::default();
+        let engine = test_engine(settings);
+        let writing = Writing::parse("x", "ab\ncd\n").unwrap();
+
+        let clock = VirtualClock::new();
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let ms = Duration::from_millis;
+        let mut events = ScriptedEvents::new(
+            &clock,
+            [
+                (ms(15), key(KeyCode::Char(' '))),
+                (ms(500), key(KeyCode::Char(' '))),
+                (ms(2000), key(KeyCode::Esc)),
+            ],
+        );
+        let mut memory = Memory::with_size((10, 3)).with_clock(&clock);
+        let step = engine
+            .play_with(&writing, &mut memory, &clock, &mut events)
+            .unwrap();
+        assert_eq!(step, Some(Step::Stop));
+        assert_eq!(clock.now(), ms(2000));
+        assert!(memory.begun && memory.finished);
+
+        let frames: Vec<_> = memory
+            .frames
+            .iter()
+            .map(|frame| {
+                let status = frame.status.split(' ').next().unwrap().to_string();
+                (frame.at.as_millis(), frame.lines.join("|"), status)
+            })
+            .collect();
+        let frame = |at, lines: &str, status: &str| (at, lines.to_string(), status.to_string());
+        assert_eq!(
+            frames,
+            [
+                frame(0, "", "▶"),
+                frame(0, "a", "▶"),
+                frame(10, "ab", "▶"),
+                frame(15, "ab", "⏸"),
+                // Resuming waits out what was left before the pause.
+                frame(500, "ab", "▶"),
+                frame(505, "ab", "▶"),
+                frame(515, "ab|c", "▶"),
+                frame(525, "ab|cd", "▶"),
+                frame(535, "ab|cd", "■"),
+            ]
+        );
+    }
+
+    #[test]
+    fn speed_changes_apply_to_the_wait_under_way() {
+        let mut settings = Settings::default();
+        settings.unit.value = Unit::Char;
+        settings.speed.value = 10;
+        settings.pauses.value = Pauses::default();
+        let engine = test_engine(settings);
+        let writing = Writing::parse("x", "abc").unwrap();
+
+        let clock = VirtualClock::new();
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let ms = Duration::from_millis;
+        let mut events = ScriptedEvents::new(
+            &clock,
+            [
+                (ms(5), key(KeyCode::Char('+'))),
+                (ms(100), key(KeyCode::Esc)),
+            ],
+        );
+        let mut memory = Memory::with_size((10, 1)).with_clock(&clock);
+        engine
+            .play_with(&writing, &mut memory, &clock, &mut events)
+            .unwrap();
+
+        // 10 ms/char becomes 8: the 5 ms left on `a` shrink to 4.
+        let shown: Vec<_> = memory
+            .frames
+            .iter()
+            .map(|frame| (frame.at.as_millis(), frame.lines.join("|")))
+            .collect();
+        let frame = |at, lines: &str| (at, lines.to_string());
+        assert_eq!(
+            shown,
+            [
+                frame(0, ""),
+                frame(0, "a"),
+                frame(5, "a"),
+                frame(9, "ab"),
+                frame(17, "abc"),
+            ]
+        );
+    }
+
+    fn screen(tui: &Tui<TestBackend>) -> Vec<String> {
+        let buffer = tui.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn title_sequences_play_through_unless_a_key_skips_them() {
+        let engine = test_engine(Settings::default());
+        let playback = Playback::new(&engine);
+        let writing = Writing::parse(
+            "x",
+            "---\ntitle: Invictus\nauthor: Henley\nyear: 1888\n---\nOut of the night\n",
+        )
+        .unwrap();
+        let opening = Sequence::opening(&writing, engine.theme(), TITLE_CARD_HOLD);
+        let mut tui = Tui::with_backend(TestBackend::new(20, 6), engine.theme()).unwrap();
+
+        let clock = VirtualClock::new();
+        let mut events = ScriptedEvents::new(&clock, []);
+        let step = run_sequence(&mut tui, &opening, &playback, &clock, &mut events).unwrap();
+        assert_eq!(step, None);
+        assert_eq!(clock.now(), cinema::FADE_IN + TITLE_CARD_HOLD);
+        assert_eq!(
+            screen(&tui),
+            [
+                "",
+                "",
+                "      Invictus",
+                "       Henley",
+                "        1888",
+                ""
+            ]
+        );
+
+        let clock = VirtualClock::new();
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        let ms = Duration::from_millis;
+        let mut events = ScriptedEvents::new(&clock, [(ms(500), key(KeyCode::Char('x')))]);
+        let step = run_sequence(&mut tui, &opening, &playback, &clock, &mut events).unwrap();
+        assert_eq!((step, clock.now()), (None, ms(500)));
+
+        let mut events = ScriptedEvents::new(&clock, [(ms(600), key(KeyCode::Esc))]);
+        let step = run_sequence(&mut tui, &opening, &playback, &clock, &mut events).unwrap();
+        assert_eq!(step, Some(Step::Stop));
+    }
+
+    #[test]
+    fn credits_scroll_up_from_below_the_screen() {
+        let engine = test_engine(Settings::default());
+        let playback = Playback::new(&engine);
+        let writing = Writing::parse("x", "---\ntitle: Night\nauthor: Henley\n---\nOut\n").unwrap();
+        let credits = Sequence::credits(&writing, engine.theme());
+        let mut tui = Tui::with_backend(TestBackend::new(30, 4), engine.theme()).unwrap();
+
+        // Stopped two rows in, then after the last frame.
+        let clock = VirtualClock::new();
+        let key = Event::Key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        let mut events = ScriptedEvents::new(
+            &clock,
+            [(cinema::CREDITS_ROW * 2 + Duration::from_millis(1), key)],
+        );
+        run_sequence(&mut tui, &credits, &playback, &clock, &mut events).unwrap();
+        assert_eq!(screen(&tui), ["", "", "             Night", ""]);
+
+        let mut events = ScriptedEvents::new(&clock, []);
+        run_sequence(&mut tui, &credits, &playback, &clock, &mut events).unwrap();
+        assert!(screen(&tui).iter().all(String::is_empty));
+    }
+}
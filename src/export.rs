@@ -0,0 +1,431 @@
+//! The `export` command: a reveal rendered offline to an animated SVG or GIF.
+//!
+//! The writing is laid out as the typewriter would lay it out, with the same
+//! wrapping, scrolling, theme colours and timing, title and section cards
+//! included. The screen has a fixed size and no status line, and the reveal
+//! runs on a virtual clock as with `--record`. `plan` turns a writing into
+//! shots, which the `svg` and `raster` modules then draw.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::engine::Playback;
+use crate::record::Size;
+use crate::reveal::{Pauses, Unit};
+use crate::scheduler::Scheduler;
+use crate::theme::{self, Theme};
+use crate::viewport;
+use crate::writing::Writing;
+use crate::{raster, svg};
+
+/// Font size in pixels when none is given.
+pub const DEFAULT_FONT_SIZE: u16 = 16;
+/// Smallest and largest `--font-size`.
+pub const FONT_SIZES: Range<u16> = 6..97;
+/// Colours used where the theme leaves them to the terminal, with a dark
+/// foreground for light backgrounds.
+const TERMINAL_FOREGROUND: [u8; 3] = [0xe5, 0xe5, 0xe5];
+const TERMINAL_BACKGROUND: [u8; 3] = [0x00, 0x00, 0x00];
+const DARK_FOREGROUND: [u8; 3] = [0x1c, 0x1c, 0x1c];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// An SVG animated with CSS keyframes
+    #[default]
+    Svg,
+    /// An animated GIF, looping
+    Gif,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Gif => "gif",
+        }
+    }
+}
+
+/// How the export looks.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub format: ExportFormat,
+    pub size: Size,
+    /// A CSS font family for SVG, or a `.ttf`/`.otf` file for GIF.
+    pub font: Option<String>,
+    pub font_size: u16,
+    /// Replaces the theme's background.
+    pub background: Option<Color>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            size: Size::default(),
+            font: None,
+            font_size: DEFAULT_FONT_SIZE,
+            background: None,
+        }
+    }
+}
+
+/// The reveal's pace, as it would be played.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Timing {
+    pub unit: Unit,
+    pub speed_ms: u64,
+    pub pauses: Pauses,
+    /// How long each title card stays up.
+    pub card: Duration,
+    /// How long the finished writing stays up at the end.
+    pub tail: Duration,
+}
+
+/// A grapheme on the export screen.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cell {
+    /// Byte offset in the shot's text; it shows once that much is revealed.
+    pub offset: usize,
+    pub grapheme: String,
+    /// Row in the shot's text, before scrolling.
+    pub row: usize,
+    pub col: usize,
+    pub style: Style,
+}
+
+/// A moment of a shot: from `at` on, the text before byte `visible` shows,
+/// scrolled so that row `top` is the first in view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Step {
+    pub at: Duration,
+    pub visible: usize,
+    pub top: usize,
+}
+
+/// A title card or the reveal of one section, on screen from `start` until
+/// `end`.
+#[derive(Debug, Clone)]
+pub(crate) struct Shot {
+    pub start: Duration,
+    pub end: Duration,
+    pub cells: Vec<Cell>,
+    /// Never empty; the first is at `start`.
+    pub steps: Vec<Step>,
+}
+
+impl Shot {
+    /// When the cell at byte `offset` first shows.
+    pub fn shown_at(&self, offset: usize) -> Duration {
+        self.steps
+            .iter()
+            .find(|step| step.visible > offset)
+            .map_or(self.end, |step| step.at)
+    }
+}
+
+/// The whole export: every shot, back to back.
+#[derive(Debug, Clone)]
+pub(crate) struct Film {
+    pub size: Size,
+    pub shots: Vec<Shot>,
+    pub length: Duration,
+    pub foreground: [u8; 3],
+    pub background: [u8; 3],
+}
+
+/// The file for `writing` revealed at `timing`, in the format and look of
+/// `options`.
+pub(crate) fn export(
+    writing: &Writing,
+    theme: &Theme,
+    timing: &Timing,
+    options: &Options,
+) -> Result<Vec<u8>> {
+    let film = plan(writing, theme, timing, options.size, options.background);
+    match options.format {
+        ExportFormat::Svg => {
+            let font = options.font.as_deref().unwrap_or(svg::DEFAULT_FONT);
+            Ok(svg::render(&film, font, options.font_size).into_bytes())
+        }
+        ExportFormat::Gif => {
+            let font = raster::load_font(options.font.as_deref().map(Path::new))?;
+            raster::render(&film, &font, options.font_size)
+        }
+    }
+}
+
+/// Lays out and times the reveal of `writing` on a screen of `size`.
+pub(crate) fn plan(
+    writing: &Writing,
+    theme: &Theme,
+    timing: &Timing,
+    size: Size,
+    background: Option<Color>,
+) -> Film {
+    let mut shots = Vec::new();
+    let mut clock = Duration::ZERO;
+    let card = |writing: &Writing, start: Duration| Shot {
+        start,
+        end: start + timing.card,
+        cells: card_cells(writing, theme, size),
+        steps: vec![Step {
+            at: start,
+            visible: usize::MAX,
+            top: 0,
+        }],
+    };
+    if writing.has_heading() {
+        shots.push(card(writing, clock));
+        clock += timing.card;
+    }
+
+    let sections = writing.sections();
+    for (index, section) in sections.iter().enumerate() {
+        if section.has_heading() {
+            shots.push(card(section, clock));
+            clock += timing.card;
+        }
+        let hold = if index + 1 == sections.len() {
+            timing.tail
+        } else {
+            timing.card
+        };
+        if section.body.trim().is_empty() {
+            clock += hold;
+            continue;
+        }
+        let shot = reveal(section, theme, timing, size, clock, hold);
+        clock = shot.end;
+        shots.push(shot);
+    }
+
+    let (foreground, background) = colors(theme, background);
+    Film {
+        size,
+        shots,
+        length: clock,
+        foreground,
+        background,
+    }
+}
+
+/// The reveal of one section, starting at `start` and held for `hold` once
+/// done.
+fn reveal(
+    writing: &Writing,
+    theme: &Theme,
+    timing: &Timing,
+    size: Size,
+    start: Duration,
+    hold: Duration,
+) -> Shot {
+    let text = writing.body.as_str();
+    let rows = viewport::wrap(text, size.width.into());
+    let cells = theme
+        .styled_graphemes(text, &writing.markup)
+        .filter_map(|(offset, grapheme, style)| {
+            let (row, col) = viewport::locate(text, &rows, offset)?;
+            Some(Cell {
+                offset,
+                grapheme: grapheme.to_string(),
+                row,
+                col,
+                style,
+            })
+        })
+        .collect();
+
+    let playback = Playback {
+        speed_ms: timing.speed_ms,
+        unit: timing.unit,
+        pauses: timing.pauses,
+        navigable: false,
+        hold: None,
+        exit_keys: &[],
+        theme,
+    };
+    let mut scheduler = Scheduler::new(writing, &playback);
+    let area = (size.width, size.height);
+    let mut clock = start;
+    let step = |scheduler: &mut Scheduler, at: Duration| Step {
+        at,
+        visible: scheduler.progress().visible().len(),
+        top: scheduler.top(area),
+    };
+
+    let mut steps = vec![step(&mut scheduler, clock)];
+    while let Some(pause) = scheduler.advance() {
+        steps.push(step(&mut scheduler, clock));
+        clock += pause;
+    }
+    Shot {
+        start,
+        end: clock + hold,
+        cells,
+        steps,
+    }
+}
+
+/// The title card's cells: the title in bold and the byline in italics,
+/// centred as on the terminal.
+fn card_cells(writing: &Writing, theme: &Theme, size: Size) -> Vec<Cell> {
+    let base = theme.base_style();
+    let middle = usize::from(size.height / 2);
+    let mut lines = vec![(
+        writing.title().to_string(),
+        base.add_modifier(Modifier::BOLD),
+        middle.saturating_sub(1),
+    )];
+    if let Some(byline) = writing.byline() {
+        lines.push((byline, base.add_modifier(Modifier::ITALIC), middle));
+    }
+
+    let mut cells = Vec::new();
+    for (line, style, row) in lines {
+        let mut col = usize::from(size.width).saturating_sub(line.width()) / 2;
+        for grapheme in line.graphemes(true) {
+            cells.push(Cell {
+                offset: 0,
+                grapheme: grapheme.to_string(),
+                row,
+                col,
+                style,
+            });
+            col += grapheme.width();
+        }
+    }
+    cells
+}
+
+/// The foreground and background `theme` shows as, on `background` if
+/// given, guessing at what the terminal would use where it leaves them open.
+pub(crate) fn colors(theme: &Theme, background: Option<Color>) -> ([u8; 3], [u8; 3]) {
+    let background = background
+        .or(theme.background)
+        .and_then(theme::rgb)
+        .unwrap_or(TERMINAL_BACKGROUND);
+    let foreground = theme::rgb(theme.foreground).unwrap_or(if is_light(background) {
+        DARK_FOREGROUND
+    } else {
+        TERMINAL_FOREGROUND
+    });
+    (foreground, background)
+}
+
+/// `fg` with `style`'s dimming, against `bg`.
+pub(crate) fn ink(style: Style, fg: [u8; 3], bg: [u8; 3]) -> [u8; 3] {
+    let fg = style.fg.and_then(theme::rgb).unwrap_or(fg);
+    if style.add_modifier.contains(Modifier::DIM) {
+        blend(bg, fg, 0.5)
+    } else {
+        fg
+    }
+}
+
+/// `from` moved `amount` of the way towards `to`.
+pub(crate) fn blend(from: [u8; 3], to: [u8; 3], amount: f32) -> [u8; 3] {
+    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * amount).round() as u8;
+    [
+        mix(from[0], to[0]),
+        mix(from[1], to[1]),
+        mix(from[2], to[2]),
+    ]
+}
+
+fn is_light([r, g, b]: [u8; 3]) -> bool {
+    299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b) > 128 * 1000
+}
+
+/// `#rrggbb`.
+pub(crate) fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// The default output path: the writing's name with the format's extension.
+pub fn default_output(writing: &Writing, format: ExportFormat) -> PathBuf {
+    let stem: String = writing
+        .name
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    PathBuf::from(format!("{stem}.{}", format.extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing() -> Timing {
+        Timing {
+            unit: Unit::Char,
+            speed_ms: 10,
+            pauses: Pauses::default(),
+            card: Duration::from_secs(3),
+            tail: Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn cards_and_sections_follow_each_other() {
+        let writing = Writing::parse("ab", "---\ntitle: AB\nauthor: Me\n---\nab\n").unwrap();
+        let film = plan(
+            &writing,
+            &Theme::default(),
+            &timing(),
+            Size::default(),
+            None,
+        );
+        assert_eq!(film.shots.len(), 2);
+
+        let card = &film.shots[0];
+        assert_eq!(card.end, Duration::from_secs(3));
+        let title: String = card.cells.iter().map(|c| c.grapheme.as_str()).collect();
+        assert_eq!(title, "ABMe");
+        assert_eq!((card.cells[0].row, card.cells[0].col), (11, 39));
+
+        let text = &film.shots[1];
+        assert_eq!(text.start, Duration::from_secs(3));
+        let times: Vec<_> = text.steps.iter().map(|s| s.at.as_millis()).collect();
+        assert_eq!(times, [3000, 3000, 3010, 3020]);
+        assert_eq!(text.shown_at(1), Duration::from_millis(3010));
+        assert_eq!(film.length, Duration::from_millis(5030));
+    }
+
+    #[test]
+    fn long_texts_scroll_to_follow_the_reveal() {
+        let writing = Writing::parse("lines", "1\n2\n3\n4\n5\n").unwrap();
+        let size = Size {
+            width: 10,
+            height: 3,
+        };
+        let film = plan(&writing, &Theme::default(), &timing(), size, None);
+        let tops: Vec<_> = film.shots[0].steps.iter().map(|s| s.top).collect();
+        assert_eq!(tops.first(), Some(&0));
+        assert_eq!(tops.last(), Some(&2));
+        assert_eq!(film.background, TERMINAL_BACKGROUND);
+
+        let film = plan(
+            &writing,
+            &Theme::default(),
+            &timing(),
+            size,
+            Some(Color::White),
+        );
+        assert_eq!(film.background, [0xff, 0xff, 0xff]);
+        assert_eq!(film.foreground, DARK_FOREGROUND);
+    }
+}
@@ -0,0 +1,57 @@
+//! Loose name matching for writings typed slightly wrong.
+
+/// Lowercases `text` and reduces everything but letters and digits to single
+/// spaces, so `The_Raven`, `the-raven` and `The Raven.` all compare equal.
+pub fn normalize(text: &str) -> String {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance between `a` and `b`, counted in characters.
+pub fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Whether `candidate` is close enough to `query` to suggest: a few typos,
+/// scaled with the length of the query, or containing it outright.
+pub fn is_close(query: &str, candidate: &str) -> bool {
+    let allowed = (query.chars().count() / 3).max(1);
+    candidate.contains(query) || distance(query, candidate) <= allowed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_ignores_case_and_punctuation() {
+        assert_eq!(normalize("The_Raven"), "the raven");
+        assert_eq!(normalize("  If— "), "if");
+        assert_eq!(normalize("古池や"), "古池や");
+    }
+
+    #[test]
+    fn distance_counts_edits() {
+        assert_eq!(distance("invictis", "invictus"), 1);
+        assert_eq!(distance("", "if"), 2);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert!(is_close("invictis", "invictus"));
+        assert!(is_close("raven", "the raven"));
+        assert!(!is_close("if", "invictus"));
+    }
+}
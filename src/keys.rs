@@ -0,0 +1,184 @@
+//! Key bindings spelled the way they are written in the config file, e.g.
+//! `q`, `esc` or `ctrl+c`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// A key plus the modifiers held with it, parsed from e.g. `"ctrl+c"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// `code` pressed with `modifiers`.
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Esc, Enter, q and Ctrl+C.
+    pub fn default_exit_keys() -> Vec<KeyBinding> {
+        vec![
+            Self::new(KeyCode::Esc, KeyModifiers::NONE),
+            Self::new(KeyCode::Enter, KeyModifiers::NONE),
+            Self::new(KeyCode::Char('q'), KeyModifiers::NONE),
+            Self::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        ]
+    }
+
+    /// A binding matches when the key is the same and at least the binding's
+    /// modifiers are held.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        key.code == self.code && key.modifiers.contains(self.modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            return Err("Key binding must not be empty".to_string());
+        }
+
+        // `+` on its own (or as the last part, e.g. `ctrl++`) is the key itself.
+        let (prefix, key) = match trimmed.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (rest.strip_suffix('+').unwrap_or(rest), "+")
+            }
+            _ => trimmed.rsplit_once('+').unwrap_or(("", trimmed)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in prefix.split('+').filter(|p| !p.is_empty()) {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => return Err(format!("unknown modifier `{other}` in `{trimmed}`")),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{key}` in `{trimmed}`")),
+                },
+            },
+        };
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        raw.parse()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn default_exit_keys_match_previous_behaviour() {
+        let exit = KeyBinding::default_exit_keys();
+        let is_exit = |event: KeyEvent| exit.iter().any(|b| b.matches(&event));
+
+        assert!(is_exit(key(KeyCode::Esc, KeyModifiers::NONE)));
+        assert!(is_exit(key(KeyCode::Enter, KeyModifiers::NONE)));
+        assert!(is_exit(key(KeyCode::Char('q'), KeyModifiers::NONE)));
+        assert!(is_exit(key(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert!(!is_exit(key(KeyCode::Char('c'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn bindings_round_trip_through_strings() {
+        for raw in [
+            "q",
+            "esc",
+            "ctrl+c",
+            "alt+shift+x",
+            "space",
+            "f5",
+            "+",
+            "ctrl++",
+        ] {
+            let binding: KeyBinding = raw.parse().unwrap();
+            assert_eq!(binding.to_string(), raw);
+        }
+
+        let binding: KeyBinding = "Ctrl+Escape".parse().unwrap();
+        assert_eq!(binding.to_string(), "ctrl+esc");
+    }
+
+    #[test]
+    fn invalid_bindings_are_rejected() {
+        assert!("".parse::<KeyBinding>().is_err());
+        assert!("hyper+q".parse::<KeyBinding>().is_err());
+        assert!("ctrl+nope".parse::<KeyBinding>().is_err());
+        assert!("f13".parse::<KeyBinding>().is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! Unveilox unveils poems and other writings in a movie roll-out style,
+//! bringing text from concealment to disclosure a grapheme, word, line or
+//! stanza at a time.
+//!
+//! The `unveilox-cli` binary is a thin layer over this library:
+//!
+//! - [`Library`] finds writings: the bundled ones, the user's and any extra
+//!   directory.
+//! - [`Writing`] is one of them, parsed from plain text or Markdown with its
+//!   front matter and inline markup.
+//! - [`RevealEngine`] plays a writing with the configured pace, mode and
+//!   theme, on the terminal, into a recording or as an animated export.
+//! - [`Renderer`] is what a reveal is drawn with; [`render`] has the
+//!   typewriter, TUI, plain and in-memory ones, all driven by the one
+//!   scheduler.
+//! - [`clock`] is where a live reveal gets its time and key presses, so tests
+//!   can play one on a virtual clock.
+//! - [`config`] and [`theme`] load the [`Settings`](config::Settings) and
+//!   [`Theme`](theme::Theme) an engine is made with, and [`playlist`] the
+//!   writings it plays back to back.
+//!
+//! # Examples
+//!
+//! ```
+//! use unveilox::{Library, RevealEngine, Writing};
+//!
+//! let writing = Writing::parse("note", "---\ntitle: A Note\n---\nHello, *world*\n")?;
+//! assert_eq!(writing.title(), "A Note");
+//! assert_eq!(writing.body, "Hello, world\n");
+//!
+//! let invictus = Library::bundled().read("invictus")?;
+//! let svg = RevealEngine::default().export(&invictus, &Default::default())?;
+//! assert!(svg.starts_with(b"<svg"));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod clock;
+pub mod config;
+pub mod export;
+pub mod keys;
+pub mod library;
+pub mod markup;
+pub mod playlist;
+pub mod record;
+pub mod render;
+pub mod reveal;
+pub mod theme;
+
+pub(crate) mod browser;
+pub(crate) mod cinema;
+pub(crate) mod controls;
+pub(crate) mod engine;
+pub(crate) mod fuzzy;
+pub(crate) mod markdown;
+pub(crate) mod raster;
+pub(crate) mod scheduler;
+pub(crate) mod svg;
+pub(crate) mod terminal;
+pub(crate) mod viewport;
+pub(crate) mod writing;
+
+pub use engine::RevealEngine;
+pub use library::Library;
+pub use playlist::Step;
+pub use render::Renderer;
+pub use writing::{Syntax, Writing};
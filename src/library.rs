@@ -0,0 +1,615 @@
+//! Poem library: the writings bundled into the binary plus any runtime
+//! directories the user keeps their own collection in.
+//!
+//! Lookup walks the layers in precedence order — an explicit `--library`
+//! directory, then the user data directory, then the bundled assets — and the
+//! first layer containing a matching name wins.
+//!
+//! A name that matches no file exactly is looked up loosely: ignoring case and
+//! punctuation, against front-matter titles too, and then as a prefix. When
+//! nothing fits, the error suggests the closest names.
+
+use std::collections::{btree_map, BTreeMap};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, bail, Context, Result};
+use include_dir::{include_dir, Dir};
+
+use crate::fuzzy;
+use crate::writing::{Syntax, Writing};
+
+static POEMS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/assets/poems");
+
+/// File extensions of writings, in the order they are looked up.
+const WRITING_EXTENSIONS: [&str; 2] = ["txt", "md"];
+
+/// Names offered after "Did you mean".
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Where a writing was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Compiled into the binary from `assets/poems`.
+    Bundled,
+    /// Loaded at runtime from a library directory.
+    Directory(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Bundled => f.write_str("bundled"),
+            Source::Directory(dir) => write!(f, "{}", dir.display()),
+        }
+    }
+}
+
+/// A writing as seen through the layered library.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    pub source: Source,
+    /// Lower-precedence layers that also contain this name.
+    pub shadows: Vec<Source>,
+}
+
+/// A writing that failed to load, left for the caller to report.
+#[derive(Debug)]
+pub struct Skipped {
+    pub name: String,
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skipping {}: {:#}", self.name, self.error)
+    }
+}
+
+/// Writings with their entries, in name order.
+pub type Loaded = Vec<(Entry, Writing)>;
+
+/// A writing a loose name could refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub name: String,
+    pub title: Option<String>,
+}
+
+impl Candidate {
+    /// The name and title, normalized for loose comparison.
+    fn keys(&self) -> Vec<String> {
+        std::iter::once(&self.name)
+            .chain(&self.title)
+            .map(|key| fuzzy::normalize(key))
+            .collect()
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.title {
+            Some(title) if fuzzy::normalize(title) != fuzzy::normalize(&self.name) => {
+                write!(f, "{} ({title})", self.name)
+            }
+            _ => f.write_str(&self.name),
+        }
+    }
+}
+
+/// The error for a name that is the prefix of several writings, so callers
+/// can offer a choice instead.
+#[derive(Debug, Clone)]
+pub struct Ambiguous {
+    pub query: String,
+    pub candidates: Vec<Candidate>,
+}
+
+impl fmt::Display for Ambiguous {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self
+            .candidates
+            .iter()
+            .map(|c| format!("`{}`", c.name))
+            .collect();
+        write!(f, "`{}` could be any of {}", self.query, names.join(", "))
+    }
+}
+
+impl std::error::Error for Ambiguous {}
+
+/// Layered view over the bundled writings and runtime library directories.
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+    dirs: Vec<PathBuf>,
+    /// Built on the first loose lookup, which has to read every writing.
+    candidates: OnceLock<Vec<Candidate>>,
+}
+
+impl Library {
+    /// Only the writings compiled into the binary.
+    pub fn bundled() -> Self {
+        Self::default()
+    }
+
+    /// Bundled writings plus the user data directory and, taking precedence
+    /// over both, an explicitly requested directory.
+    pub fn discover(explicit: Option<&Path>) -> Result<Self> {
+        let mut library = Self::bundled();
+
+        if let Some(dir) = explicit {
+            if !dir.is_dir() {
+                bail!("Library directory not found: {}", dir.display());
+            }
+            library.dirs.push(dir.to_path_buf());
+        }
+
+        if let Some(dir) = default_user_dir() {
+            if dir.is_dir() && !library.dirs.contains(&dir) {
+                library.dirs.push(dir);
+            }
+        }
+
+        Ok(library)
+    }
+
+    /// Every distinct writing, sorted by name, attributed to the layer that
+    /// wins for that name.
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        Ok(self
+            .located()?
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect())
+    }
+
+    /// Every writing with its entry, in name order, and apart from them
+    /// those that failed to load.
+    pub fn read_all(&self) -> Result<(Loaded, Vec<Skipped>)> {
+        let mut writings = Vec::new();
+        let mut skipped = Vec::new();
+        for (entry, file) in self.located()? {
+            match file.load() {
+                Ok(writing) => writings.push((entry, writing)),
+                Err(error) => skipped.push(Skipped {
+                    name: entry.name,
+                    error,
+                }),
+            }
+        }
+        Ok((writings, skipped))
+    }
+
+    /// Loads the writing called `name`, header and all. A name no file has
+    /// fails, suggesting the closest ones.
+    pub fn read(&self, name: &str) -> Result<Writing> {
+        let name = checked(name)?;
+        match self.read_exact(name)? {
+            Some(writing) => Ok(writing),
+            None => Err(self.not_found(name)),
+        }
+    }
+
+    /// Like [`Library::read`], but falls back to a loose match when no file
+    /// has that name; see [`Library::resolve`].
+    pub fn read_fuzzy(&self, query: &str) -> Result<Writing> {
+        let query = checked(query)?;
+        match self.read_exact(query)? {
+            Some(writing) => Ok(writing),
+            None => self.read(&self.resolve(query)?),
+        }
+    }
+
+    fn read_exact(&self, name: &str) -> Result<Option<Writing>> {
+        for dir in &self.dirs {
+            if let Some(path) = find_in_dir(dir, name)? {
+                return File::Path(path).load().map(Some);
+            }
+        }
+
+        find_bundled(name)
+            .map(|file| File::Bundled(file).load())
+            .transpose()
+    }
+
+    /// The name of the one writing `query` loosely refers to.
+    ///
+    /// Names and titles are compared without case or punctuation, first
+    /// whole and then by prefix. Several prefix matches fail with
+    /// [`Ambiguous`]; no match at all fails with the closest names by edit
+    /// distance or substring as suggestions.
+    pub fn resolve(&self, query: &str) -> Result<String> {
+        let wanted = fuzzy::normalize(query);
+        if wanted.is_empty() {
+            bail!("Writing not found: {query}");
+        }
+
+        let candidates = self.candidates()?;
+        let whole: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|c| c.keys().contains(&wanted))
+            .collect();
+        let prefixed: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|c| c.keys().iter().any(|key| key.starts_with(&wanted)))
+            .collect();
+        for matches in [whole, prefixed] {
+            match matches.as_slice() {
+                [] => continue,
+                [only] => return Ok(only.name.clone()),
+                _ => {
+                    return Err(Ambiguous {
+                        query: query.to_string(),
+                        candidates: matches.into_iter().cloned().collect(),
+                    }
+                    .into())
+                }
+            }
+        }
+        Err(self.not_found(query))
+    }
+
+    /// The error for a name that matches no writing, with the closest names
+    /// by edit distance or substring as suggestions. Only file names are
+    /// compared, so nothing has to be read.
+    fn not_found(&self, query: &str) -> anyhow::Error {
+        let wanted = fuzzy::normalize(query);
+        let entries = match self.entries() {
+            Ok(entries) if !wanted.is_empty() => entries,
+            _ => return anyhow!("Writing not found: {query}"),
+        };
+        let mut close: Vec<(usize, &str)> = entries
+            .iter()
+            .map(|entry| (fuzzy::normalize(&entry.name), entry.name.as_str()))
+            .filter(|(key, _)| fuzzy::is_close(&wanted, key))
+            .map(|(key, name)| (fuzzy::distance(&wanted, &key), name))
+            .collect();
+        close.sort_by_key(|(distance, _)| *distance);
+        let names: Vec<_> = close
+            .iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, name)| format!("`{name}`"))
+            .collect();
+        match names.as_slice() {
+            [] => anyhow!("Writing not found: {query}"),
+            [one] => anyhow!("Writing not found: {query}. Did you mean {one}?"),
+            _ => anyhow!(
+                "Writing not found: {query}. Did you mean one of {}?",
+                names.join(", ")
+            ),
+        }
+    }
+
+    /// Every entry with its front-matter title. Writings that fail to load
+    /// are still candidates by name.
+    fn candidates(&self) -> Result<&[Candidate]> {
+        if let Some(candidates) = self.candidates.get() {
+            return Ok(candidates);
+        }
+        let candidates = self
+            .located()?
+            .into_iter()
+            .map(|(entry, file)| Candidate {
+                name: entry.name,
+                title: file.load().ok().and_then(|w| w.title),
+            })
+            .collect();
+        Ok(self.candidates.get_or_init(|| candidates))
+    }
+
+    /// Every distinct writing with the file it is read from, in name order.
+    /// Each directory is listed once.
+    fn located(&self) -> Result<Vec<(Entry, File)>> {
+        let mut located: BTreeMap<String, (Entry, File)> = BTreeMap::new();
+        for (source, files) in self.layers()? {
+            for file in files {
+                let name = file.stem().to_string();
+                match located.entry(name.to_ascii_lowercase()) {
+                    btree_map::Entry::Occupied(mut winner) => {
+                        winner.get_mut().0.shadows.push(source.clone())
+                    }
+                    btree_map::Entry::Vacant(slot) => {
+                        let entry = Entry {
+                            name,
+                            source: source.clone(),
+                            shadows: Vec::new(),
+                        };
+                        slot.insert((entry, file));
+                    }
+                }
+            }
+        }
+
+        let mut located: Vec<_> = located.into_values().collect();
+        located.sort_unstable_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        Ok(located)
+    }
+
+    fn layers(&self) -> Result<Vec<(Source, Vec<File>)>> {
+        let mut layers = Vec::with_capacity(self.dirs.len() + 1);
+        for dir in &self.dirs {
+            let files = dir_files(dir)?.into_iter().map(File::Path).collect();
+            layers.push((Source::Directory(dir.clone()), distinct(files)));
+        }
+        let bundled = POEMS
+            .files()
+            .filter(|f| is_writing(f.path()))
+            .map(File::Bundled)
+            .collect();
+        layers.push((Source::Bundled, distinct(bundled)));
+        Ok(layers)
+    }
+}
+
+/// The file a writing is read from.
+enum File {
+    Path(PathBuf),
+    Bundled(&'static include_dir::File<'static>),
+}
+
+impl File {
+    fn path(&self) -> &Path {
+        match self {
+            File::Path(path) => path,
+            File::Bundled(file) => file.path(),
+        }
+    }
+
+    fn stem(&self) -> &str {
+        stem(self.path()).unwrap_or_default()
+    }
+
+    fn load(&self) -> Result<Writing> {
+        match self {
+            File::Path(path) => Writing::from_path(path),
+            File::Bundled(file) => {
+                let origin = file.path().display().to_string();
+                let text = decode(file.contents().to_vec(), &origin)?;
+                Writing::parse_as(self.stem(), &text, Syntax::of(file.path()))
+                    .with_context(|| format!("in {origin}"))
+            }
+        }
+    }
+}
+
+/// `name` trimmed, which must not be empty.
+fn checked(name: &str) -> Result<&str> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        bail!("Writing name must not be empty");
+    }
+    Ok(trimmed)
+}
+
+/// Reads an arbitrary text file, rejecting content that is not UTF-8.
+pub(crate) fn read_path(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    decode(bytes, &path.display().to_string())
+}
+
+/// Reads all of standard input, which must be piped rather than a terminal.
+pub fn read_stdin() -> Result<String> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        bail!("standard input is a terminal; pipe text in, e.g. `cat notes.txt | unveilox-cli -`");
+    }
+
+    let mut bytes = Vec::new();
+    stdin.read_to_end(&mut bytes)?;
+    decode(bytes, "standard input")
+}
+
+fn decode(bytes: Vec<u8>, origin: &str) -> Result<String> {
+    let text = String::from_utf8(bytes).map_err(|err| {
+        anyhow!(
+            "{origin} is not valid UTF-8 (invalid byte at offset {})",
+            err.utf8_error().valid_up_to()
+        )
+    })?;
+
+    if text.trim().is_empty() {
+        bail!("{origin} contains no text to unveil");
+    }
+
+    Ok(text)
+}
+
+/// `$XDG_DATA_HOME/unveilox/poems`, falling back to
+/// `~/.local/share/unveilox/poems`.
+pub fn default_user_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("unveilox").join("poems"))
+}
+
+fn is_writing(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            WRITING_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+        .unwrap_or(false)
+}
+
+/// Files sorted by stem, with a writing kept as both `.txt` and `.md`
+/// listed once, as its `.txt`.
+fn distinct(mut files: Vec<File>) -> Vec<File> {
+    files.sort_unstable_by(|a, b| {
+        let plain = |file: &File| Syntax::of(file.path()) != Syntax::Plain;
+        a.stem().cmp(b.stem()).then(plain(a).cmp(&plain(b)))
+    });
+    files.dedup_by(|a, b| a.stem().eq_ignore_ascii_case(b.stem()));
+    files
+}
+
+fn stem(path: &Path) -> Option<&str> {
+    path.file_stem().and_then(|s| s.to_str())
+}
+
+fn find_bundled(name: &str) -> Option<&'static include_dir::File<'static>> {
+    // First try exact matches, .txt before .md
+    for extension in WRITING_EXTENSIONS {
+        if let Some(file) = POEMS.get_file(format!("{name}.{extension}")) {
+            return Some(file);
+        }
+    }
+
+    POEMS.files().find(|f| {
+        is_writing(f.path())
+            && stem(f.path())
+                .map(|s| s.eq_ignore_ascii_case(name))
+                .unwrap_or(false)
+    })
+}
+
+fn dir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let read_dir = fs::read_dir(dir)
+        .with_context(|| format!("failed to read library directory {}", dir.display()))?;
+
+    let mut files = Vec::new();
+    for entry in read_dir {
+        let path = entry?.path();
+        if path.is_file() && is_writing(&path) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+fn find_in_dir(dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    for extension in WRITING_EXTENSIONS {
+        let exact = dir.join(format!("{name}.{extension}"));
+        if exact.is_file() {
+            return Ok(Some(exact));
+        }
+    }
+
+    let mut files = dir_files(dir)?;
+    files.sort_unstable_by_key(|p| Syntax::of(p) != Syntax::Plain);
+    Ok(files.into_iter().find(|p| {
+        stem(p)
+            .map(|s| s.eq_ignore_ascii_case(name))
+            .unwrap_or(false)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library_in(dir: &Path) -> Library {
+        Library {
+            dirs: vec![dir.to_path_buf()],
+            ..Library::default()
+        }
+    }
+
+    #[test]
+    fn user_directory_shadows_bundled_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Invictus.txt"), "my own invictus").unwrap();
+        fs::write(dir.path().join("ozymandias.txt"), "I met a traveller").unwrap();
+
+        let library = library_in(dir.path());
+        assert_eq!(library.read("invictus").unwrap().body, "my own invictus");
+        assert_eq!(
+            library.read("ozymandias").unwrap().body,
+            "I met a traveller"
+        );
+        let bundled = library.read("if").unwrap();
+        assert_eq!(bundled.name, "if");
+        assert_eq!(bundled.author.as_deref(), Some("Rudyard Kipling"));
+
+        let entries = library.entries().unwrap();
+        let invictus = entries.iter().find(|e| e.name == "Invictus").unwrap();
+        assert_eq!(invictus.source, Source::Directory(dir.path().to_path_buf()));
+        assert_eq!(invictus.shadows, vec![Source::Bundled]);
+        assert!(entries.iter().all(|e| e.name != "invictus"));
+    }
+
+    #[test]
+    fn markdown_writings_are_read_and_other_files_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.rst"), "draft").unwrap();
+        fs::write(dir.path().join("ode.md"), "# Ode\n\n**Loud** words").unwrap();
+        fs::write(dir.path().join("both.md"), "# Markdown\nwords").unwrap();
+        fs::write(dir.path().join("both.txt"), "plain words").unwrap();
+
+        let library = library_in(dir.path());
+        assert!(library.read("notes").is_err());
+        let ode = library.read("ode").unwrap();
+        assert_eq!(ode.title(), "Ode");
+        assert_eq!(ode.body, "Loud words\n");
+        assert_eq!(library.read("both").unwrap().body, "plain words");
+
+        let entries = library.entries().unwrap();
+        assert!(entries.iter().all(|e| e.name != "notes"));
+        assert_eq!(entries.iter().filter(|e| e.name == "both").count(), 1);
+        assert!(entries.iter().any(|e| e.name == "ode"));
+    }
+
+    #[test]
+    fn loose_names_resolve_or_suggest() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("ozy.txt"),
+            "---\ntitle: Ozymandias of Egypt\n---\nI met a traveller",
+        )
+        .unwrap();
+        fs::write(dir.path().join("invocation.txt"), "Sing, goddess").unwrap();
+
+        let library = library_in(dir.path());
+        assert_eq!(
+            library.read_fuzzy("Ozymandias of Egypt").unwrap().name,
+            "ozy"
+        );
+        assert_eq!(library.read_fuzzy("ozymandias").unwrap().name, "ozy");
+        assert_eq!(library.read_fuzzy("invict").unwrap().name, "invictus");
+
+        let err = library.read_fuzzy("inv").unwrap_err();
+        let ambiguous = err.downcast_ref::<Ambiguous>().unwrap();
+        let names: Vec<_> = ambiguous
+            .candidates
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["invictus", "invocation"]);
+
+        let err = library.read_fuzzy("invictis").unwrap_err().to_string();
+        assert_eq!(err, "Writing not found: invictis. Did you mean `invictus`?");
+        let err = library.read_fuzzy("zzz").unwrap_err().to_string();
+        assert_eq!(err, "Writing not found: zzz");
+
+        // Exact reads never settle for a loose match, but still suggest one.
+        let err = library.read("invict").unwrap_err().to_string();
+        assert_eq!(err, "Writing not found: invict. Did you mean `invictus`?");
+        assert!(library.read("ozymandias").is_err());
+    }
+
+    #[test]
+    fn non_utf8_file_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latin1.txt");
+        fs::write(&path, b"caf\xe9").unwrap();
+
+        let err = read_path(&path).unwrap_err();
+        assert!(err.to_string().contains("not valid UTF-8"));
+        assert!(err.to_string().contains("offset 3"));
+        assert!(read_path(&dir.path().join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn missing_explicit_directory_is_an_error() {
+        let err = Library::discover(Some(Path::new("/definitely/not/here"))).unwrap_err();
+        assert!(err.to_string().contains("Library directory not found"));
+    }
+}
@@ -0,0 +1,290 @@
+//! The `list` command: a filterable, sortable catalogue of the library.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+use unveilox::config::Settings;
+use unveilox::library::{Entry, Library, Skipped};
+use unveilox::reveal;
+use unveilox::Writing;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Title,
+    Author,
+    Year,
+    /// Longest reveal first.
+    Length,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+}
+
+/// Which writings to list and in what order.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Case-insensitive part of the author's name.
+    pub author: Option<String>,
+    /// Tags a writing must all carry, case-insensitively.
+    pub tags: Vec<String>,
+    pub sort: SortKey,
+}
+
+impl Query {
+    fn matches(&self, writing: &Writing) -> bool {
+        let author = self.author.as_ref().is_none_or(|wanted| {
+            writing
+                .author
+                .as_ref()
+                .is_some_and(|author| author.to_lowercase().contains(&wanted.to_lowercase()))
+        });
+        let tags = self.tags.iter().all(|wanted| {
+            writing
+                .tags
+                .iter()
+                .any(|tag| tag.eq_ignore_ascii_case(wanted))
+        });
+        author && tags
+    }
+}
+
+/// One listed writing.
+#[derive(Debug, Clone, Serialize)]
+pub struct Row {
+    pub name: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub year: Option<i32>,
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    /// Lines with text on them; blank lines between stanzas don't count.
+    pub lines: usize,
+    /// How long the reveal takes with the current speed, unit and pauses.
+    pub reading_ms: u64,
+    pub source: String,
+    /// Lower-precedence layers holding a writing of the same name.
+    pub overrides: Vec<String>,
+}
+
+impl Row {
+    pub fn new(entry: &Entry, writing: &Writing, settings: &Settings) -> Self {
+        let reading = settings.reveal_duration(writing);
+        Self {
+            name: entry.name.clone(),
+            title: writing.title().to_string(),
+            author: writing.author.clone(),
+            year: writing.year,
+            tags: writing.tags.clone(),
+            language: writing.language.clone(),
+            lines: writing.line_count(),
+            reading_ms: u64::try_from(reading.as_millis()).unwrap_or(u64::MAX),
+            source: entry.source.to_string(),
+            overrides: entry.shadows.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// Reads every writing in `library` and keeps those matching `query`.
+/// Writings that fail to load are returned apart.
+pub fn rows(
+    library: &Library,
+    query: &Query,
+    settings: &Settings,
+) -> Result<(Vec<Row>, Vec<Skipped>)> {
+    let (writings, skipped) = library.read_all()?;
+    let mut rows: Vec<_> = writings
+        .iter()
+        .filter(|(_, writing)| query.matches(writing))
+        .map(|(entry, writing)| Row::new(entry, writing, settings))
+        .collect();
+    sort(&mut rows, query.sort);
+    Ok((rows, skipped))
+}
+
+fn sort(rows: &mut [Row], key: SortKey) {
+    // Missing authors and years go last.
+    fn optional<T: Ord>(a: &Option<T>, b: &Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    rows.sort_by(|a, b| {
+        let primary = match key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::Author => optional(
+                &a.author.as_ref().map(|s| s.to_lowercase()),
+                &b.author.as_ref().map(|s| s.to_lowercase()),
+            ),
+            SortKey::Year => optional(&a.year, &b.year),
+            SortKey::Length => b.reading_ms.cmp(&a.reading_ms),
+        };
+        primary.then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+pub fn render(rows: &[Row], format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(rows)? + "\n",
+        Format::Table => table(rows),
+    })
+}
+
+fn table(rows: &[Row]) -> String {
+    let header = ["NAME", "TITLE", "AUTHOR", "LINES", "TIME", "SOURCE"];
+    let cells: Vec<[String; 6]> = rows
+        .iter()
+        .map(|row| {
+            let mut source = row.source.clone();
+            if !row.overrides.is_empty() {
+                source.push_str(&format!(" (overrides {})", row.overrides.join(", ")));
+            }
+            [
+                row.name.clone(),
+                row.title.clone(),
+                row.author.clone().unwrap_or_else(|| "-".to_string()),
+                row.lines.to_string(),
+                reveal::reading_time(Duration::from_millis(row.reading_ms)),
+                source,
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let mut out = String::new();
+    let header = header.map(str::to_string);
+    for row in std::iter::once(&header).chain(&cells) {
+        let mut line = String::new();
+        for (column, (cell, width)) in row.iter().zip(widths).enumerate() {
+            if column > 0 {
+                line.push_str("  ");
+            }
+            let pad = width - cell.width();
+            // Numbers line up on the right.
+            if matches!(column, 3 | 4) {
+                line.push_str(&" ".repeat(pad));
+                line.push_str(cell);
+            } else {
+                line.push_str(cell);
+                line.push_str(&" ".repeat(pad));
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unveilox::library::Source;
+    use unveilox::reveal::{Pauses, Unit};
+
+    fn row(name: &str, text: &str) -> Row {
+        let entry = Entry {
+            name: name.to_string(),
+            source: Source::Bundled,
+            shadows: Vec::new(),
+        };
+        let mut settings = Settings::default();
+        settings.pauses.value = Pauses::default();
+        Row::new(&entry, &Writing::parse(name, text).unwrap(), &settings)
+    }
+
+    #[test]
+    fn query_filters_by_author_and_every_tag() {
+        let kipling = Writing::parse(
+            "if",
+            "---\nauthor: Rudyard Kipling\ntags: [Stoic, advice]\n---\nIf\n",
+        )
+        .unwrap();
+        let anonymous = Writing::parse("notes", "Some words\n").unwrap();
+
+        let query = |author: Option<&str>, tags: &[&str]| Query {
+            author: author.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            sort: SortKey::Name,
+        };
+        assert!(query(Some("kipling"), &["stoic"]).matches(&kipling));
+        assert!(!query(Some("kipling"), &["stoic", "love"]).matches(&kipling));
+        assert!(!query(Some("henley"), &[]).matches(&kipling));
+        assert!(!query(Some("kipling"), &[]).matches(&anonymous));
+        assert!(query(None, &[]).matches(&anonymous));
+    }
+
+    #[test]
+    fn rows_count_lines_and_reading_time_and_sort() {
+        let long = row("long", "---\nyear: 1910\n---\none\ntwo\n\nthree\n");
+        assert_eq!(long.lines, 3);
+        assert_eq!(long.reading_ms, 15 * 25);
+
+        let short = row("short", "---\nyear: 1875\n---\nhi\n");
+        let undated = row("b-undated", "words\n");
+        let mut rows = vec![short.clone(), long.clone(), undated.clone()];
+
+        sort(&mut rows, SortKey::Length);
+        assert_eq!(rows[0].name, "long");
+        sort(&mut rows, SortKey::Year);
+        let names: Vec<_> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["short", "long", "b-undated"]);
+        sort(&mut rows, SortKey::Name);
+        assert_eq!(rows[0].name, "b-undated");
+    }
+
+    #[test]
+    fn reading_time_follows_the_configured_unit() {
+        let entry = Entry {
+            name: "night".to_string(),
+            source: Source::Bundled,
+            shadows: Vec::new(),
+        };
+        let writing = Writing::parse("night", "Out of the night, black\nas the pit\n").unwrap();
+        let mut settings = Settings::default();
+        let by_char = Row::new(&entry, &writing, &settings).reading_ms;
+        // A line at a time, the comma inside the line no longer pauses.
+        settings.unit.value = Unit::Line;
+        let by_line = Row::new(&entry, &writing, &settings).reading_ms;
+        assert_eq!(by_char - by_line, 120);
+    }
+
+    #[test]
+    fn table_aligns_columns_and_json_has_every_field() {
+        let rows = vec![row(
+            "haiku",
+            "---\ntitle: 古池\nauthor: Bashō\n---\n古池や\n",
+        )];
+        let table = render(&rows, Format::Table).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        let column = |line: &str, cell: &str| line[..line.find(cell).unwrap()].width();
+        assert_eq!(column(lines[0], "AUTHOR"), column(lines[1], "Bashō"));
+        assert!(lines[1].contains("  1  "));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&rows, Format::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["title"], "古池");
+        assert_eq!(json[0]["lines"], 1);
+        assert_eq!(json[0]["source"], "bundled");
+    }
+}
@@ -1,37 +1,79 @@
-use std::io::{self, Write};
+mod listing;
+mod pick;
+mod search;
+
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
-use clap::Parser;
-use crossterm::{
-    cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    style::{self, Stylize},
-    terminal::{self, ClearType},
-};
-use include_dir::{include_dir, Dir};
-use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Style},
-    text::Text,
-    widgets::{Block, Borders, Paragraph, Wrap},
-    Terminal,
-};
-
-static POEMS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/assets/poems");
-
-const DEFAULT_SPEED: u64 = 25;
-const MIN_SPEED: u64 = 1;
-const MAX_SPEED: u64 = 1_000;
+use clap::{Args, Parser, Subcommand};
+use ratatui::style::Color;
+use unveilox::config::{self, Config, Mode, Overrides, PauseTable, Settings};
+use unveilox::export::{self, ExportFormat};
+use unveilox::playlist::Playlist;
+use unveilox::reveal::Unit;
+use unveilox::theme::{self, Theme};
+use unveilox::{library, record, Library, RevealEngine, Writing};
+
+use crate::listing::{Format, Query, SortKey};
+
+const DEFAULT_DELAY: &str = "3";
+const MAX_DELAY_SECS: f64 = 60.0;
 
 #[derive(Debug, Clone)]
 enum Action {
     Help,
-    List,
+    Browse,
+    List {
+        query: Query,
+        format: Format,
+    },
     Show(String),
+    File(PathBuf),
+    Stdin,
+    Playlist {
+        names: Vec<String>,
+        file: Option<PathBuf>,
+        delay: Duration,
+    },
+    Search {
+        query: String,
+        options: search::Options,
+        context: usize,
+        play: bool,
+    },
+    Random {
+        query: Query,
+        seed: Option<u64>,
+    },
+    Daily {
+        query: Query,
+        seed: Option<u64>,
+    },
+    Export {
+        name: Option<String>,
+        file: Option<PathBuf>,
+        output: Option<PathBuf>,
+        options: export::Options,
+    },
+    ConfigShow,
+}
+
+impl Action {
+    /// Whether the action unveils exactly one writing, as `--record` needs.
+    fn unveils_one(&self) -> bool {
+        matches!(
+            self,
+            Action::Show(_)
+                | Action::File(_)
+                | Action::Stdin
+                | Action::Search { play: true, .. }
+                | Action::Random { .. }
+                | Action::Daily { .. }
+        )
+    }
 }
 
 impl FromStr for Action {
@@ -46,13 +88,94 @@ impl FromStr for Action {
         if trimmed.eq_ignore_ascii_case("help") {
             Ok(Action::Help)
         } else if trimmed.eq_ignore_ascii_case("list") {
-            Ok(Action::List)
+            Ok(Action::List {
+                query: Query::default(),
+                format: Format::default(),
+            })
+        } else if trimmed == "-" {
+            Ok(Action::Stdin)
         } else {
             Ok(Action::Show(trimmed.to_string()))
         }
     }
 }
 
+impl From<Command> for Action {
+    fn from(command: Command) -> Self {
+        match command {
+            Command::Show {
+                file: Some(path), ..
+            } => Action::File(path),
+            Command::Show {
+                name: Some(name), ..
+            } if name.trim() == "-" => Action::Stdin,
+            Command::Show { name, .. } => Action::Show(name.unwrap_or_default()),
+            Command::List {
+                filter,
+                sort,
+                format,
+            } => Action::List {
+                query: Query {
+                    sort,
+                    ..filter.into()
+                },
+                format,
+            },
+            Command::Random { filter, seed } => Action::Random {
+                query: filter.into(),
+                seed,
+            },
+            Command::Daily { filter, seed } => Action::Daily {
+                query: filter.into(),
+                seed,
+            },
+            Command::Search {
+                query,
+                ignore_case,
+                word,
+                regex,
+                context,
+                play,
+            } => Action::Search {
+                query,
+                options: search::Options {
+                    ignore_case,
+                    whole_word: word,
+                    regex,
+                },
+                context,
+                play,
+            },
+            Command::Playlist { names, file, delay } => Action::Playlist { names, file, delay },
+            Command::Browse => Action::Browse,
+            Command::Export {
+                name,
+                file,
+                format,
+                output,
+                font,
+                font_size,
+                size,
+                background,
+            } => Action::Export {
+                name,
+                file,
+                output,
+                options: export::Options {
+                    format,
+                    size,
+                    font,
+                    font_size,
+                    background,
+                },
+            },
+            Command::Config {
+                command: ConfigCommand::Show,
+            } => Action::ConfigShow,
+        }
+    }
+}
+
 fn parse_action(raw: &str) -> std::result::Result<Action, String> {
     Action::from_str(raw)
 }
@@ -62,82 +185,40 @@ fn parse_speed(raw: &str) -> std::result::Result<u64, String> {
         .parse()
         .map_err(|_| format!("`{raw}` is not a valid positive integer"))?;
 
-    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+    config::validate_speed(speed)
+}
+
+fn parse_font_size(raw: &str) -> std::result::Result<u16, String> {
+    let size: u16 = raw
+        .parse()
+        .map_err(|_| format!("`{raw}` is not a valid font size"))?;
+
+    if export::FONT_SIZES.contains(&size) {
+        Ok(size)
+    } else {
         Err(format!(
-            "speed must be between {MIN_SPEED} and {MAX_SPEED} milliseconds"
+            "font size must be between {} and {} pixels",
+            export::FONT_SIZES.start,
+            export::FONT_SIZES.end - 1
         ))
-    } else {
-        Ok(speed)
     }
 }
 
-struct TerminalGuard {
-    raw_mode: bool,
-    alt_screen: bool,
-    cursor_hidden: bool,
+fn parse_background(raw: &str) -> std::result::Result<Color, String> {
+    theme::parse_color(raw).map_err(|err| err.to_string())
 }
 
-impl TerminalGuard {
-    fn enter(hide_cursor: bool) -> Result<Self> {
-        let mut stdout = io::stdout();
-        execute!(stdout, terminal::EnterAlternateScreen)?;
-        terminal::enable_raw_mode()?;
-
-        if hide_cursor {
-            execute!(stdout, cursor::Hide)?;
-        }
-
-        Ok(Self {
-            raw_mode: true,
-            alt_screen: true,
-            cursor_hidden: hide_cursor,
-        })
-    }
-
-    fn clear(&self) -> Result<()> {
-        let mut stdout = io::stdout();
-        execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
-        Ok(())
-    }
-
-    fn show_cursor(&mut self) -> Result<()> {
-        if self.cursor_hidden {
-            let mut stdout = io::stdout();
-            execute!(stdout, cursor::Show)?;
-            self.cursor_hidden = false;
-        }
-        Ok(())
-    }
-
-    fn disable_raw_mode(&mut self) -> Result<()> {
-        if self.raw_mode {
-            terminal::disable_raw_mode()?;
-            self.raw_mode = false;
-        }
-        Ok(())
-    }
-
-    fn leave_alt_screen(&mut self) -> Result<()> {
-        if self.alt_screen {
-            let mut stdout = io::stdout();
-            execute!(stdout, terminal::LeaveAlternateScreen)?;
-            self.alt_screen = false;
-        }
-        Ok(())
-    }
-
-    fn finish(&mut self) -> Result<()> {
-        self.show_cursor()?;
-        self.disable_raw_mode()?;
-        self.leave_alt_screen()
-    }
-}
+fn parse_delay(raw: &str) -> std::result::Result<Duration, String> {
+    let secs: f64 = raw
+        .parse()
+        .map_err(|_| format!("`{raw}` is not a valid number of seconds"))?;
 
-impl Drop for TerminalGuard {
-    fn drop(&mut self) {
-        let _ = self.show_cursor();
-        let _ = self.disable_raw_mode();
-        let _ = self.leave_alt_screen();
+    if !(0.0..=MAX_DELAY_SECS).contains(&secs) {
+        Err(format!(
+            "delay must be between 0 and {MAX_DELAY_SECS} seconds"
+        ))
+    } else {
+        Ok(Duration::from_secs_f64(secs))
     }
 }
 
@@ -145,225 +226,555 @@ impl Drop for TerminalGuard {
 #[command(
     name = "unveilox-cli",
     version,
-    about = "Unveils poems/writings in a movie roll-out style - bringing text from concealment to disclosure"
+    about = "Unveils poems/writings in a movie roll-out style - bringing text from concealment to disclosure",
+    args_conflicts_with_subcommands = true,
+    disable_help_subcommand = true
 )]
 struct Cli {
-    /// One of: help | list | <poem_name>
-    #[arg(value_name = "ACTION", value_parser = parse_action, default_value = "help")]
-    action: Action,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// One of: help | list | <poem_name> | - (read standard input);
+    /// without one the library browser opens
+    #[arg(value_name = "ACTION", value_parser = parse_action)]
+    action: Option<Action>,
 
-    /// Milliseconds per character (typewriter mode)
-    #[arg(long, short, default_value_t = DEFAULT_SPEED, value_parser = parse_speed)]
-    speed: u64,
+    /// Milliseconds per character [default: 25]
+    #[arg(long, short, global = true, value_parser = parse_speed)]
+    speed: Option<u64>,
 
     /// Use the TUI animation instead of plain typewriter
-    #[arg(long)]
+    #[arg(long, global = true)]
     tui: bool,
-}
 
-fn list_poems() {
-    let mut names: Vec<_> = POEMS
-        .files()
-        .filter_map(|f| {
-            f.path()
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .map(str::to_string)
-        })
-        .collect();
+    /// Use the plain typewriter even if the config selects the TUI
+    #[arg(long, global = true, conflicts_with = "tui")]
+    typewriter: bool,
 
-    names.sort_unstable();
+    /// Reveal by char, word, line or stanza (paced by --speed per character)
+    #[arg(long, global = true, value_name = "UNIT")]
+    unit: Option<Unit>,
 
-    if names.is_empty() {
-        println!("No writings bundled. Add files under assets/poems/.");
-        return;
-    }
+    /// Keep an even pace instead of pausing at punctuation, line ends and stanzas
+    #[arg(long, global = true)]
+    no_pauses: bool,
 
-    println!("Available writings:");
-    for name in names {
-        println!("- {name}");
-    }
-}
+    /// Print the text without escape sequences (automatic when stdout is not a terminal)
+    #[arg(long, global = true, conflicts_with_all = ["tui", "typewriter"])]
+    plain: bool,
 
-fn read_poem(name: &str) -> Result<String> {
-    let trimmed = name.trim();
-    if trimmed.is_empty() {
-        bail!("Writing name must not be empty");
-    }
+    /// Keep the per-character delay in plain output instead of printing at once
+    #[arg(long, global = true)]
+    paced: bool,
 
-    // First try exact match with .txt
-    let filename = format!("{trimmed}.txt");
-    if let Some(file) = POEMS.get_file(&filename) {
-        return Ok(String::from_utf8_lossy(file.contents()).into_owned());
-    }
+    /// Roll closing credits after a TUI reveal
+    #[arg(long, global = true)]
+    credits: bool,
 
-    if let Some(file) = POEMS.files().find(|f| {
-        f.path()
-            .file_stem()
-            .and_then(|stem| stem.to_str())
-            .map(|stem| stem.eq_ignore_ascii_case(trimmed))
-            .unwrap_or(false)
-    }) {
-        return Ok(String::from_utf8_lossy(file.contents()).into_owned());
-    }
+    /// Presentation profile from the config file
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Colour theme: default, mono, high-contrast, solarized, gradient, per-word,
+    /// a theme in ~/.config/unveilox/themes, or a path to a .toml theme file
+    #[arg(long, global = true, value_name = "THEME")]
+    theme: Option<String>,
+
+    /// Config file to use instead of ~/.config/unveilox/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
 
-    bail!("Writing not found: {trimmed}");
+    /// Extra directory of writings, searched before the user and bundled ones
+    #[arg(long, global = true, value_name = "DIR")]
+    library: Option<PathBuf>,
+
+    /// Write the reveal to an asciicast file instead of playing it; needs no terminal
+    #[arg(long, global = true, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Screen size for --record [default: 80x24]
+    #[arg(long, global = true, value_name = "COLSxROWS", requires = "record")]
+    record_size: Option<record::Size>,
 }
 
-fn typewriter_print(text: &str, speed_ms: u64) -> Result<()> {
-    let mut guard = TerminalGuard::enter(true)?;
-    guard.clear()?;
-
-    let mut stdout = io::stdout();
-    let mut col: u16 = 0;
-    let mut row: u16 = 0;
-    let mut exit_requested = false;
-
-    for ch in text.chars() {
-        match ch {
-            '\n' => {
-                col = 0;
-                row = row.saturating_add(1);
-                execute!(&mut stdout, cursor::MoveTo(col, row))?;
-            }
-            _ => {
-                let styled = if (col as usize + row as usize) % 7 == 0 {
-                    format!("{}", ch.with(style::Color::Magenta))
-                } else if (col as usize) % 5 == 0 {
-                    format!("{}", ch.with(style::Color::Blue))
-                } else {
-                    ch.to_string()
-                };
-                write!(&mut stdout, "{styled}")?;
-                stdout.flush()?;
-                col = col.saturating_add(1);
-            }
-        }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Unveil a named writing, a text file or standard input
+    Show {
+        /// Name of a writing in the library, or `-` for standard input
+        #[arg(value_name = "POEM", required_unless_present = "file")]
+        name: Option<String>,
+
+        /// Unveil the contents of this file instead of a library writing
+        #[arg(long, short, value_name = "PATH", conflicts_with = "name")]
+        file: Option<PathBuf>,
+    },
+    /// List the writings in the library
+    List {
+        #[command(flatten)]
+        filter: Filter,
+
+        /// Order of the listing
+        #[arg(long, value_enum, default_value_t)]
+        sort: SortKey,
+
+        /// Output as an aligned table or as JSON for scripts
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Find writings containing a phrase
+    Search {
+        /// Text to look for (a regular expression with --regex)
+        #[arg(value_name = "QUERY")]
+        query: String,
+
+        /// Match regardless of case
+        #[arg(long, short = 'i')]
+        ignore_case: bool,
+
+        /// Only match whole words
+        #[arg(long, short = 'w')]
+        word: bool,
+
+        /// Treat the query as a regular expression
+        #[arg(long, short = 'e')]
+        regex: bool,
+
+        /// Lines of context to show around each match
+        #[arg(long, short = 'C', value_name = "LINES", default_value_t = 1)]
+        context: usize,
+
+        /// Unveil the first match, starting at its stanza
+        #[arg(long)]
+        play: bool,
+    },
+    /// Play several writings one after another
+    Playlist {
+        /// Names of writings to play, in order
+        #[arg(value_name = "POEM", required_unless_present = "file")]
+        names: Vec<String>,
+
+        /// Read the writing names from a playlist file (one per line, `#` comments)
+        #[arg(long, short, value_name = "PATH", conflicts_with = "names")]
+        file: Option<PathBuf>,
+
+        /// Seconds to hold each title card and finished writing
+        #[arg(long, default_value = DEFAULT_DELAY, value_parser = parse_delay)]
+        delay: Duration,
+    },
+    /// Browse the library and unveil writings from it
+    Browse,
+    /// Unveil a writing picked at random
+    Random {
+        #[command(flatten)]
+        filter: Filter,
+
+        /// Pick with this seed instead of the clock, for a repeatable choice
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+    },
+    /// Unveil the writing of the day, the same one all day (UTC)
+    Daily {
+        #[command(flatten)]
+        filter: Filter,
+
+        /// Pick as if this were the day number (days since 1970-01-01)
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+    },
+    /// Render a reveal offline to an animated SVG or GIF
+    Export {
+        /// Name of a writing in the library, or `-` for standard input
+        #[arg(value_name = "POEM", required_unless_present = "file")]
+        name: Option<String>,
+
+        /// Export the contents of this file instead of a library writing
+        #[arg(long, short, value_name = "PATH", conflicts_with = "name")]
+        file: Option<PathBuf>,
+
+        /// Animated SVG or GIF
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+
+        /// Where to write it [default: the writing's name with the format's extension]
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// A CSS font family for SVG, or a .ttf/.otf file for GIF [default: DejaVu Sans Mono]
+        #[arg(long, value_name = "FONT")]
+        font: Option<String>,
+
+        /// Font size in pixels
+        #[arg(long, value_name = "PX", default_value_t = export::DEFAULT_FONT_SIZE, value_parser = parse_font_size)]
+        font_size: u16,
+
+        /// Screen size in cells
+        #[arg(long, value_name = "COLSxROWS", default_value_t)]
+        size: record::Size,
+
+        /// Background colour instead of the theme's, such as `white` or `#fdf6e3`
+        #[arg(long, value_name = "COLOR", value_parser = parse_background)]
+        background: Option<Color>,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+/// Which writings `list`, `random` and `daily` choose from.
+#[derive(Args, Debug)]
+struct Filter {
+    /// Only writings whose author contains this (case-insensitive)
+    #[arg(long, value_name = "NAME")]
+    author: Option<String>,
 
-        if poll_for_exit(Duration::from_millis(speed_ms))? {
-            exit_requested = true;
-            break;
+    /// Only writings carrying this tag; repeat to require several
+    #[arg(long, value_name = "TAG")]
+    tag: Vec<String>,
+}
+
+impl From<Filter> for Query {
+    fn from(filter: Filter) -> Self {
+        Query {
+            author: filter.author,
+            tags: filter.tag,
+            sort: SortKey::default(),
         }
     }
+}
 
-    stdout.flush()?;
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the effective settings after merging config, profile and flags
+    Show,
+}
 
-    if !exit_requested {
-        while !poll_for_exit(Duration::from_millis(100))? {}
+/// Reports the writings a command left out because they failed to load.
+fn warn_skipped(skipped: &[library::Skipped]) {
+    for skipped in skipped {
+        eprintln!("warning: {skipped}");
     }
-
-    guard.finish()?;
-    Ok(())
 }
 
-fn tui_reveal(text: &str) -> Result<()> {
-    let mut guard = TerminalGuard::enter(true)?;
-
-    let backend = CrosstermBackend::new(io::stdout());
-    let mut terminal = Terminal::new(backend)?;
-    terminal.hide_cursor()?;
-
-    let total_chars = text.chars().count();
-    let start = Instant::now();
-
-    loop {
-        // Increment reveal over time (about 120 chars/sec)
-        let elapsed = start.elapsed().as_millis() as usize;
-        let shown = (elapsed / 8).min(total_chars);
-
-        // Build visible text safely by char count
-        let visible: String = text.chars().take(shown).collect();
-
-        terminal.draw(|f| {
-            let size = f.size();
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Percentage(100)].as_ref())
-                .split(size);
-
-            let block = Block::default()
-                .borders(Borders::ALL)
-                .title("unveilox-cli — press q to quit");
-
-            // Slightly "cinematic" centered title with soft color
-            let paragraph = Paragraph::new(Text::from(visible))
-                .block(block)
-                .wrap(Wrap { trim: false })
-                .alignment(Alignment::Left)
-                .style(Style::default().fg(Color::White));
-
-            f.render_widget(paragraph, chunks[0]);
-        })?;
-
-        // Early exit
-        if event::poll(Duration::from_millis(16))? {
-            match event::read()? {
-                Event::Key(k) if is_exit_key(&k) => break,
-                Event::Resize(_, _) => {}
-                _ => {}
-            }
-        }
+fn list_poems(library: &Library, query: &Query, format: Format, settings: &Settings) -> Result<()> {
+    let (rows, skipped) = listing::rows(library, query, settings)?;
+    warn_skipped(&skipped);
 
-        if shown >= total_chars {
-            // After full reveal, wait for quit
-            if event::poll(Duration::from_millis(100))? {
-                match event::read()? {
-                    Event::Key(k) if is_exit_key(&k) => break,
-                    Event::Resize(_, _) => {}
-                    _ => {}
-                }
-            }
+    if rows.is_empty() && format == Format::Table {
+        if query.author.is_some() || !query.tags.is_empty() {
+            println!("No writings match the given filters.");
+        } else {
+            println!("No writings found. Add files under assets/poems/ or a library directory.");
         }
+        return Ok(());
     }
 
-    terminal.show_cursor()?;
-    guard.finish()?;
+    print!("{}", listing::render(&rows, format)?);
     Ok(())
 }
 
 fn main() -> Result<()> {
-    let Cli { action, speed, tui } = Cli::parse();
+    let Cli {
+        command,
+        action,
+        speed,
+        tui,
+        typewriter,
+        unit,
+        no_pauses,
+        plain,
+        paced,
+        credits,
+        profile,
+        theme,
+        config,
+        library,
+        record,
+        record_size,
+    } = Cli::parse();
+    let action = command
+        .map(Action::from)
+        .or(action)
+        .unwrap_or(if io::stdout().is_terminal() {
+            Action::Browse
+        } else {
+            Action::Help
+        });
+    let action = match action {
+        Action::Browse if !io::stdout().is_terminal() => Action::List {
+            query: Query::default(),
+            format: Format::default(),
+        },
+        action => action,
+    };
+    let library = Library::discover(library.as_deref())?;
+    if record.is_some() && !action.unveils_one() {
+        bail!("--record needs a single writing to unveil, as in `show invictus --record out.cast`");
+    }
+    let overrides = Overrides {
+        speed,
+        mode: match (tui, typewriter, plain) {
+            (true, _, _) => Some(Mode::Tui),
+            (_, true, _) => Some(Mode::Typewriter),
+            (_, _, true) => Some(Mode::Plain),
+            _ => None,
+        },
+        unit,
+        theme,
+        pauses: no_pauses.then(PauseTable::off),
+        credits: credits.then_some(true),
+    };
+    // The config and theme are only read by the actions that need them, so
+    // a broken one does not also take `help` and `list` down.
+    let load_config = || Config::load(config.as_deref());
+    let settings = || load_config()?.resolve(profile.as_deref(), overrides.clone());
+    // Listings still come out with a broken config, just at the defaults.
+    let listing_settings = || {
+        settings().or_else(|err| {
+            eprintln!("warning: ignoring the config: {err:#}");
+            Config::default().resolve(None, overrides.clone())
+        })
+    };
+    let engine = || -> Result<RevealEngine> {
+        let settings = settings()?;
+        let theme = Theme::load(&settings.theme.value)?;
+        let mut engine = RevealEngine::new(settings, theme).with_paced(paced);
+        if let Some(path) = record.clone() {
+            engine = engine.with_record(record::Target {
+                path,
+                size: record_size.unwrap_or_default(),
+            });
+        }
+        Ok(engine)
+    };
 
     match action {
         Action::Help => {
-            println!("Usage: unveilox-cli [help|list|<poem_name>] [--speed N] [--tui]");
+            println!(
+                "Usage: unveilox-cli [help|list|<poem_name>|-] [--speed N] [--tui] [--theme NAME] [--profile NAME] [--library DIR]"
+            );
+            println!("       unveilox-cli list [--author NAME] [--tag TAG] [--sort KEY] [--format table|json]");
+            println!("       unveilox-cli show [<poem_name>|--file PATH] [--speed N] [--tui]");
+            println!("       unveilox-cli search <query> [-i] [-w] [--regex] [-C N] [--play]");
+            println!("       unveilox-cli playlist [<poem_name>...|--file PATH] [--delay SECS]");
+            println!("       unveilox-cli random|daily [--author NAME] [--tag TAG] [--seed N]");
+            println!("       unveilox-cli export [<poem_name>|--file PATH] [--format svg|gif] [-o PATH] [--font FONT] [--font-size PX] [--size COLSxROWS] [--background COLOR]");
+            println!("       unveilox-cli browse");
+            println!("       unveilox-cli config show");
             println!("Examples:");
+            println!("  unveilox-cli             (browse the library)");
             println!("  unveilox-cli list");
             println!("  unveilox-cli invictus");
+            println!("  unveilox-cli invict          (a unique prefix or title works too)");
             println!("  unveilox-cli the_raven --tui");
+            println!("  unveilox-cli if --tui --unit line --speed 40");
+            println!("  unveilox-cli invictus --no-pauses");
+            println!("  unveilox-cli list --library ~/poems");
+            println!("  unveilox-cli list --tag stoic --sort length --format json");
+            println!("  unveilox-cli show --file ./speech.txt");
+            println!("  cat notes.txt | unveilox-cli -");
+            println!("  unveilox-cli search -i \"captain of my soul\" --play");
+            println!("  unveilox-cli playlist invictus if --delay 5");
+            println!("  unveilox-cli random --tag stoic");
+            println!("  unveilox-cli daily --plain");
+            println!("  unveilox-cli invictus --profile stage");
+            println!("  unveilox-cli config show");
+            println!("  unveilox-cli if --theme solarized");
+            println!("  unveilox-cli invictus --plain --paced > reading.log");
+            println!("  unveilox-cli invictus --tui --record invictus.cast");
+            println!("  unveilox-cli invictus --tui --credits");
+            println!(
+                "  unveilox-cli export invictus --format gif --font-size 20 --background white"
+            );
+            println!();
+            println!(
+                "While revealing: space pause, +/- speed, right/tab finish stanza, s reveal all"
+            );
+            println!("When done: up/down, pgup/pgdn, home/end or the mouse wheel scroll");
+            println!("In the TUI: any key skips the title sequence or the credits");
+            if let Some(dir) = library::default_user_dir() {
+                println!();
+                println!("User writings are also read from {}", dir.display());
+            }
             Ok(())
         }
-        Action::List => {
-            list_poems();
+        Action::Browse => {
+            warn_skipped(&engine()?.browse(&library)?);
             Ok(())
         }
+        Action::List { query, format } => {
+            list_poems(&library, &query, format, &listing_settings()?)
+        }
         Action::Show(name) => {
-            let poem = read_poem(&name).with_context(|| format!("while reading '{name}'"))?;
-            if tui {
-                tui_reveal(&poem)
-            } else {
-                typewriter_print(&poem, speed)
+            let engine = engine()?;
+            match engine.read(&library, &name)? {
+                Some(writing) => engine.reveal(&writing),
+                None => Ok(()),
             }
         }
+        Action::File(path) => {
+            let writing = Writing::from_path(&path)
+                .with_context(|| format!("while reading '{}'", path.display()))?;
+            engine()?.reveal(&writing)
+        }
+        Action::Stdin => {
+            let text = library::read_stdin().context("while reading standard input")?;
+            let writing = Writing::parse("standard input", &text)?;
+            engine()?.reveal(&writing)
+        }
+        Action::Playlist { names, file, delay } => {
+            let playlist = match file {
+                Some(path) => Playlist::from_file(&path)?,
+                None => Playlist::new(names)?,
+            };
+            engine()?.play_playlist(&library, &playlist, delay)
+        }
+        Action::Search {
+            query,
+            options,
+            context,
+            play,
+        } => {
+            let pattern = search::Pattern::new(&query, options)?;
+            let (found, skipped) = search::search(&library, &pattern)?;
+            warn_skipped(&skipped);
+            let Some(first) = found.first() else {
+                eprintln!("No writing contains `{query}`.");
+                return Ok(());
+            };
+
+            if play {
+                let start = search::stanza_start(&first.writing.body, first.lines[0].offset);
+                return engine()?.reveal(&first.writing.starting_at(start));
+            }
+
+            let highlight = io::stdout().is_terminal()
+                && listing_settings()?.mode.value != Mode::Plain
+                && !theme::no_color_requested();
+            print!("{}", search::render(&found, context, highlight));
+            Ok(())
+        }
+        Action::Random { query, seed } => play_pick(
+            &library,
+            &query,
+            seed.unwrap_or_else(pick::clock_seed),
+            &engine()?,
+        ),
+        Action::Daily { query, seed } => play_pick(
+            &library,
+            &query,
+            seed.unwrap_or_else(pick::today),
+            &engine()?,
+        ),
+        Action::Export {
+            name,
+            file,
+            output,
+            options,
+        } => {
+            let engine = engine()?;
+            let writing = match (file, name) {
+                (Some(path), _) => Writing::from_path(&path)
+                    .with_context(|| format!("while reading '{}'", path.display()))?,
+                (None, Some(name)) if name.trim() == "-" => {
+                    let text = library::read_stdin().context("while reading standard input")?;
+                    Writing::parse("standard input", &text)?
+                }
+                (None, name) => match engine.read(&library, &name.unwrap_or_default())? {
+                    Some(writing) => writing,
+                    None => return Ok(()),
+                },
+            };
+            let output = output.unwrap_or_else(|| export::default_output(&writing, options.format));
+            let bytes = engine.export(&writing, &options)?;
+            std::fs::write(&output, bytes)
+                .with_context(|| format!("failed to write {}", output.display()))?;
+            eprintln!("Exported '{}' to {}", writing.title(), output.display());
+            Ok(())
+        }
+        Action::ConfigShow => {
+            let config = load_config()?;
+            show_config(&config, &config.resolve(profile.as_deref(), overrides)?);
+            Ok(())
+        }
     }
 }
 
-fn is_exit_key(key: &KeyEvent) -> bool {
-    match key.code {
-        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => true,
-        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
-        _ => false,
-    }
+/// Unveils the writing `seed` picks out of those matching `query`.
+fn play_pick(library: &Library, query: &Query, seed: u64, engine: &RevealEngine) -> Result<()> {
+    let settings = engine.settings();
+    let (rows, skipped) = listing::rows(library, query, settings)?;
+    warn_skipped(&skipped);
+    let Some(row) = pick::choose(&rows, seed) else {
+        eprintln!("No writings match the given filters.");
+        return Ok(());
+    };
+    let writing = library
+        .read(&row.name)
+        .with_context(|| format!("while reading '{}'", row.name))?;
+    engine.reveal(&writing)
 }
 
-fn poll_for_exit(timeout: Duration) -> Result<bool> {
-    if event::poll(timeout)? {
-        if let Event::Key(key) = event::read()? {
-            return Ok(is_exit_key(&key));
-        }
+fn show_config(config: &Config, settings: &Settings) {
+    match &config.path {
+        Some(path) if config.loaded => println!("# config: {}", path.display()),
+        Some(path) => println!("# config: {} (not found)", path.display()),
+        None => println!("# config: none"),
+    }
+    let profiles: Vec<_> = config.profile_names().collect();
+    if !profiles.is_empty() {
+        println!("# profiles: {}", profiles.join(", "));
+    }
+    if let Some(profile) = &settings.profile {
+        println!("profile = \"{profile}\"");
     }
 
-    Ok(false)
+    let pauses = settings.pauses.value;
+    let exit_keys: Vec<_> = settings
+        .exit_keys
+        .value
+        .iter()
+        .map(|key| format!("\"{key}\""))
+        .collect();
+    let lines = [
+        (
+            format!("speed = {}", settings.speed.value),
+            &settings.speed.origin,
+        ),
+        (
+            format!("mode = \"{}\"", settings.mode.value),
+            &settings.mode.origin,
+        ),
+        (
+            format!("unit = \"{}\"", settings.unit.value),
+            &settings.unit.origin,
+        ),
+        (
+            format!("theme = \"{}\"", settings.theme.value),
+            &settings.theme.origin,
+        ),
+        (
+            format!("exit_keys = [{}]", exit_keys.join(", ")),
+            &settings.exit_keys.origin,
+        ),
+        (
+            format!(
+                "pauses = {{ comma = {}, period = {}, dash = {}, line = {}, stanza = {} }}",
+                pauses.comma, pauses.period, pauses.dash, pauses.line, pauses.stanza
+            ),
+            &settings.pauses.origin,
+        ),
+        (
+            format!("credits = {}", settings.credits.value),
+            &settings.credits.origin,
+        ),
+    ];
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, origin) in lines {
+        println!("{line:<width$}  # {origin}");
+    }
 }
 
 #[cfg(test)]
@@ -373,11 +784,49 @@ mod tests {
     #[test]
     fn action_from_str_parses_variants() {
         assert!(matches!(Action::from_str("help").unwrap(), Action::Help));
-        assert!(matches!(Action::from_str("LIST").unwrap(), Action::List));
+        assert!(matches!(
+            Action::from_str("LIST").unwrap(),
+            Action::List { .. }
+        ));
         match Action::from_str("Invictus").unwrap() {
             Action::Show(name) => assert_eq!(name, "Invictus"),
             _ => panic!("expected show variant"),
         }
+        assert!(matches!(Action::from_str(" - ").unwrap(), Action::Stdin));
+    }
+
+    #[test]
+    fn show_subcommand_resolves_to_action() {
+        let cli = Cli::try_parse_from(["unveilox-cli", "show", "--file", "speech.txt", "--tui"])
+            .expect("show --file should parse");
+        assert!(cli.tui);
+        match cli.command.map(Action::from) {
+            Some(Action::File(path)) => assert_eq!(path, PathBuf::from("speech.txt")),
+            other => panic!("expected file action, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["unveilox-cli", "show"]).is_err());
+        assert!(Cli::try_parse_from(["unveilox-cli", "show", "if", "--file", "x.txt"]).is_err());
+    }
+
+    #[test]
+    fn random_and_daily_take_list_filters_and_a_seed() {
+        let cli = Cli::try_parse_from(["unveilox-cli", "random", "--tag", "stoic", "--seed", "7"])
+            .expect("random should parse");
+        match cli.command.map(Action::from) {
+            Some(Action::Random { query, seed }) => {
+                assert_eq!(query.tags, ["stoic"]);
+                assert_eq!(seed, Some(7));
+            }
+            other => panic!("expected random action, got {other:?}"),
+        }
+
+        let cli = Cli::try_parse_from(["unveilox-cli", "daily", "--author", "henley"])
+            .expect("daily should parse");
+        assert!(matches!(
+            cli.command.map(Action::from),
+            Some(Action::Daily { seed: None, .. })
+        ));
     }
 
     #[test]
@@ -388,16 +837,30 @@ mod tests {
         assert!(parse_speed("not-a-number").is_err());
     }
 
+    #[test]
+    fn parse_delay_accepts_fractional_seconds() {
+        assert_eq!(parse_delay("1.5").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_delay("0").unwrap(), Duration::ZERO);
+        assert!(parse_delay("-1").is_err());
+        assert!(parse_delay("61").is_err());
+    }
+
     #[test]
     fn poem_lookup_is_case_insensitive() {
-        let lower = read_poem("invictus").expect("poem should load");
-        let upper = read_poem("INVICtus").expect("poem should load");
+        let lower = Library::bundled()
+            .read("invictus")
+            .expect("poem should load");
+        let upper = Library::bundled()
+            .read("INVICtus")
+            .expect("poem should load");
         assert_eq!(lower, upper);
     }
 
     #[test]
     fn empty_poem_name_is_rejected() {
-        let err = read_poem("   ").expect_err("empty name must fail");
+        let err = Library::bundled()
+            .read("   ")
+            .expect_err("empty name must fail");
         assert!(err.to_string().contains("must not be empty"));
     }
 }
@@ -0,0 +1,277 @@
+//! Markdown writings: `.md` files, read alongside `.txt` ones.
+//!
+//! Only what reads well unveiled is understood:
+//!
+//! | Markdown | unveiled as |
+//! | --- | --- |
+//! | `# Heading`, at any level | a title card before the section it opens |
+//! | `*italic*`, `_italic_` | italic |
+//! | `**bold**`, `__bold__` | bold |
+//! | `> quote` | indented, a step per `>` |
+//! | `---`, `***`, `___` | a stanza break |
+//!
+//! A heading before any text titles the whole writing instead. Lines are kept
+//! as written, as a poem's would be, and anything else, such as lists or
+//! links, shows as typed. Inline [markup] works as in `.txt`.
+
+use anyhow::Result;
+
+use crate::markup::{self, Markup};
+
+/// How far each level of a blockquote is indented.
+const QUOTE_INDENT: &str = "    ";
+
+/// A Markdown text taken apart.
+#[derive(Debug, Default)]
+pub struct Document {
+    /// The heading before any text, if there is one.
+    pub title: Option<String>,
+    /// The text of every section, a blank line apart.
+    pub body: String,
+    pub markup: Markup,
+    /// The other headings, by the byte offset in `body` of the section each
+    /// one opens.
+    pub headings: Vec<(usize, String)>,
+}
+
+impl Document {
+    /// Adds the section written in `source`, which starts on `first_line`.
+    fn push(&mut self, heading: Option<String>, source: &str, first_line: usize) -> Result<()> {
+        let trimmed = source.trim_start_matches('\n');
+        let first_line = first_line + source.len() - trimmed.len();
+        let (text, markup) = markup::parse_from(trimmed, first_line)?;
+        let (mut text, mut markup) = single_spaced(&text, &markup);
+        if text.trim().is_empty() {
+            if heading.is_none() {
+                return Ok(());
+            }
+            text.clear();
+            markup = Markup::default();
+        }
+
+        if !self.body.is_empty() {
+            while !self.body.ends_with("\n\n") {
+                self.body.push('\n');
+            }
+        }
+        let offset = self.body.len();
+        if let Some(heading) = heading {
+            self.headings.push((offset, heading));
+        }
+        self.markup.append(markup, offset);
+        self.body.push_str(&text);
+        Ok(())
+    }
+}
+
+/// Takes `source` apart at its headings. Errors come from the inline markup.
+pub fn parse(source: &str) -> Result<Document> {
+    let mut document = Document::default();
+    let mut heading: Option<String> = None;
+    let mut section = String::new();
+    let mut first_line = 1;
+
+    for (index, line) in source.lines().enumerate() {
+        let Some(text) = heading_text(line) else {
+            section.push_str(&block(line));
+            section.push('\n');
+            continue;
+        };
+        let untitled = document.title.is_none() && document.body.is_empty();
+        if untitled && heading.is_none() && section.trim().is_empty() {
+            document.title = Some(text);
+        } else {
+            document.push(heading.replace(text), &section, first_line)?;
+        }
+        section.clear();
+        first_line = index + 2;
+    }
+    document.push(heading, &section, first_line)?;
+    Ok(document)
+}
+
+/// `text` with runs of blank lines cut to one, as Markdown reads them, and
+/// none at the end.
+fn single_spaced(text: &str, markup: &Markup) -> (String, Markup) {
+    let mut spaced = String::with_capacity(text.len());
+    let mut spaced_markup = Markup::default();
+    let mut offset = 0;
+    let mut blank = false;
+    for line in text.split_inclusive('\n') {
+        let range = offset..offset + line.len();
+        offset += line.len();
+        let was_blank = std::mem::replace(&mut blank, line.trim().is_empty());
+        if !(blank && was_blank) {
+            spaced_markup.append(markup.within(range), spaced.len());
+            spaced.push_str(line);
+        }
+    }
+    spaced.truncate(spaced.trim_end().len());
+    if !spaced.is_empty() {
+        spaced.push('\n');
+    }
+    (spaced, spaced_markup)
+}
+
+/// The plain text of an ATX heading, such as `## Part II ##`.
+fn heading_text(line: &str) -> Option<String> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return None;
+    }
+    let text = rest.trim_start_matches('#');
+    let level = rest.len() - text.len();
+    if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let text = text.trim();
+    let unclosed = text.trim_end_matches('#');
+    let text = if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        unclosed.trim_end()
+    } else {
+        text
+    };
+    let source = underscores(text);
+    let plain = markup::parse(&source).map_or(source, |(plain, _)| plain);
+    let plain = plain.trim();
+    (!plain.is_empty()).then(|| plain.to_string())
+}
+
+/// The markup source for a line that is not a heading.
+fn block(line: &str) -> String {
+    if is_rule(line) {
+        return String::new();
+    }
+
+    let mut depth = 0;
+    let mut rest = line;
+    while let Some(inner) = rest.trim_start_matches(' ').strip_prefix('>') {
+        depth += 1;
+        rest = inner.strip_prefix(' ').unwrap_or(inner);
+    }
+    if depth > 0 && rest.trim().is_empty() {
+        return String::new();
+    }
+    format!("{}{}", QUOTE_INDENT.repeat(depth), underscores(rest))
+}
+
+/// `---`, `***` or `___`, spaced or not.
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|ch| !ch.is_whitespace()).collect();
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|mark| marks.iter().all(|ch| ch == mark))
+}
+
+/// `_italic_` and `__bold__` rewritten with asterisks for the markup parser.
+/// Underscores inside words, as in `snake_case`, are left alone, and `\_`
+/// stands for an underscore.
+fn underscores(line: &str) -> String {
+    let mut chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&'_') => {
+                chars.remove(i);
+                i += 1;
+            }
+            '_' => {
+                let run = run_at(&chars, i);
+                if let Some(close) = closing(&chars, i, run) {
+                    chars[i..i + run].fill('*');
+                    chars[close..close + run].fill('*');
+                }
+                i += run;
+            }
+            _ => i += 1,
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn run_at(chars: &[char], at: usize) -> usize {
+    chars[at..].iter().take_while(|&&ch| ch == '_').count()
+}
+
+/// Where the run of `run` underscores opening at `open` is closed.
+fn closing(chars: &[char], open: usize, run: usize) -> Option<usize> {
+    let starts_word = open == 0 || !chars[open - 1].is_alphanumeric();
+    let first = chars.get(open + run)?;
+    if run > 2 || !starts_word || first.is_whitespace() {
+        return None;
+    }
+
+    let mut at = open + run + 1;
+    while at < chars.len() {
+        if chars[at] != '_' {
+            at += 1;
+            continue;
+        }
+        let len = run_at(chars, at);
+        let before = chars[at - 1];
+        let ends_word = chars.get(at + len).is_none_or(|ch| !ch.is_alphanumeric());
+        if len == run && !before.is_whitespace() && before != '\\' && ends_word {
+            return Some(at);
+        }
+        at += len;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::Look;
+
+    #[test]
+    fn headings_split_the_text_into_sections() {
+        let document = parse(
+            "# The Poem\n\n## Part I\nFirst line\n\n## Part II ##\n\nSecond line\n## Empty\n",
+        )
+        .unwrap();
+        assert_eq!(document.title.as_deref(), Some("The Poem"));
+        assert_eq!(document.body, "First line\n\nSecond line\n\n");
+        let headings: Vec<_> = document
+            .headings
+            .iter()
+            .map(|(at, heading)| (*at, heading.as_str()))
+            .collect();
+        assert_eq!(headings, [(0, "Part I"), (12, "Part II"), (25, "Empty")]);
+
+        let untitled = parse("Prelude\n# C#\nText\n").unwrap();
+        assert_eq!(untitled.title, None);
+        assert_eq!(untitled.headings, [(9, "C#".to_string())]);
+        assert_eq!(heading_text("#hashtag"), None);
+        assert_eq!(heading_text("    # code"), None);
+    }
+
+    #[test]
+    fn emphasis_quotes_and_rules() {
+        let document =
+            parse("A _soft_ and __loud__ snake_case\n> quoted\n> > twice\n---\n* * *\nafter\n")
+                .unwrap();
+        assert_eq!(
+            document.body,
+            "A soft and loud snake_case\n    quoted\n        twice\n\nafter\n"
+        );
+        let looks: Vec<_> = document
+            .markup
+            .styles
+            .iter()
+            .map(|styled| (styled.range.clone(), styled.look))
+            .collect();
+        assert_eq!(looks, [(2..6, Look::Emphasis), (11..15, Look::Strong)]);
+
+        assert_eq!(underscores(r"\_not\_ _a_b_ __x_"), "_not_ *a_b* __x_");
+    }
+
+    #[test]
+    fn markup_errors_point_at_the_source_line() {
+        let err = parse("# Title\n\nline\n{pause:2}\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 4:"), "{err}");
+        let err = parse("# Title\n## Part\n\n{color:red}x\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 4:"), "{err}");
+    }
+}
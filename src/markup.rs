@@ -0,0 +1,393 @@
+//! Inline markup authors can put in a writing's text:
+//!
+//! | markup | effect |
+//! | --- | --- |
+//! | `{pause:2s}`, `{pause:500ms}` | holds the reveal at that point |
+//! | `{speed:80}` … `{/speed}` | milliseconds per character for the passage |
+//! | `*emphasis*` | italic |
+//! | `**strong**` | bold |
+//! | `{color:red}` … `{/color}` | colours the passage |
+//!
+//! `\{`, `\}`, `\*` and `\\` stand for the character itself, and braces that
+//! don't make one of these tags are kept as they are. The markup is taken out
+//! of the text, so `list`, `search` and plain output only see the words; the
+//! reveal reads it back from [`Markup`], by byte offset into that text.
+
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::config;
+
+/// The longest `{pause:…}` accepted.
+pub(crate) const MAX_PAUSE: Duration = Duration::from_secs(60);
+
+/// How a marked passage looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Look {
+    Emphasis,
+    Strong,
+    Color(Color),
+}
+
+impl Look {
+    /// `style` with this look on top. Colours are left out when `colored` is
+    /// false, as under `NO_COLOR`.
+    pub(crate) fn apply(self, style: Style, colored: bool) -> Style {
+        match self {
+            Look::Emphasis => style.add_modifier(Modifier::ITALIC),
+            Look::Strong => style.add_modifier(Modifier::BOLD),
+            Look::Color(color) if colored => style.fg(color),
+            Look::Color(_) => style,
+        }
+    }
+}
+
+/// A passage of the text with a [`Look`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Styled {
+    pub range: Range<usize>,
+    pub look: Look,
+}
+
+/// What the markup in a text asked for, by byte offset into the text it was
+/// taken out of.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Markup {
+    /// Holds, each after the text up to its offset.
+    pub pauses: Vec<(usize, Duration)>,
+    /// The speed from each offset on; `None` returns to the reader's own.
+    pub speeds: Vec<(usize, Option<u64>)>,
+    pub styles: Vec<Styled>,
+}
+
+impl Markup {
+    /// `base` with the looks of every passage holding `offset`.
+    pub(crate) fn style_at(&self, offset: usize, base: Style, colored: bool) -> Style {
+        self.styles
+            .iter()
+            .filter(|styled| styled.range.contains(&offset))
+            .fold(base, |style, styled| styled.look.apply(style, colored))
+    }
+
+    /// The speed asked for at `offset`, if any.
+    pub fn speed_at(&self, offset: usize) -> Option<u64> {
+        self.speeds
+            .iter()
+            .take_while(|(at, _)| *at <= offset)
+            .last()
+            .and_then(|(_, speed)| *speed)
+    }
+
+    /// The holds placed after `from` and up to `to`.
+    pub fn pause_within(&self, from: usize, to: usize) -> Duration {
+        self.pauses
+            .iter()
+            .filter(|(at, _)| from < *at && *at <= to)
+            .map(|(_, pause)| *pause)
+            .sum()
+    }
+
+    /// The markup for the text from byte `offset` on.
+    pub fn starting_at(&self, offset: usize) -> Self {
+        self.within(offset..usize::MAX)
+    }
+
+    /// The markup for the bytes of the text in `range`.
+    pub fn within(&self, range: Range<usize>) -> Self {
+        let Range { start, end } = range;
+        let speeds = self
+            .speed_at(start)
+            .map(|speed| (0, Some(speed)))
+            .into_iter()
+            .chain(
+                self.speeds
+                    .iter()
+                    .filter(|(at, _)| start < *at && *at < end)
+                    .map(|&(at, speed)| (at - start, speed)),
+            )
+            .collect();
+        Self {
+            pauses: self
+                .pauses
+                .iter()
+                .filter(|(at, _)| start < *at && *at <= end)
+                .map(|&(at, pause)| (at - start, pause))
+                .collect(),
+            speeds,
+            styles: self
+                .styles
+                .iter()
+                .filter(|styled| styled.range.end > start && styled.range.start < end)
+                .map(|styled| Styled {
+                    range: styled.range.start.saturating_sub(start)
+                        ..styled.range.end.min(end) - start,
+                    look: styled.look,
+                })
+                .collect(),
+        }
+    }
+
+    /// Adds the markup of a text appended at byte `offset`.
+    pub(crate) fn append(&mut self, other: Markup, offset: usize) {
+        self.pauses.extend(
+            other
+                .pauses
+                .into_iter()
+                .map(|(at, pause)| (at + offset, pause)),
+        );
+        self.speeds.extend(
+            other
+                .speeds
+                .into_iter()
+                .map(|(at, speed)| (at + offset, speed)),
+        );
+        self.styles
+            .extend(other.styles.into_iter().map(|styled| Styled {
+                range: styled.range.start + offset..styled.range.end + offset,
+                look: styled.look,
+            }));
+    }
+}
+
+/// Splits `source` into its plain text and the markup in it.
+pub(crate) fn parse(source: &str) -> Result<(String, Markup)> {
+    parse_from(source, 1)
+}
+
+/// [`parse`] for a `source` that starts on line `first_line` of a larger
+/// text, so errors point at the right line.
+pub(crate) fn parse_from(source: &str, first_line: usize) -> Result<(String, Markup)> {
+    let mut text = String::with_capacity(source.len());
+    let mut markup = Markup::default();
+    let mut colors: Vec<(usize, Color, usize)> = Vec::new();
+    let mut emphasis: Option<usize> = None;
+    let mut strong: Option<usize> = None;
+    let mut line = first_line;
+    let mut rest = source;
+
+    while let Some(ch) = rest.chars().next() {
+        let after = &rest[ch.len_utf8()..];
+        match ch {
+            '\\' if after.starts_with(['{', '}', '*', '\\']) => {
+                let escaped = after.chars().next().unwrap_or('\\');
+                text.push(escaped);
+                rest = &after[escaped.len_utf8()..];
+                continue;
+            }
+            '{' => {
+                if let Some((tag, tail)) = after.split_once('}') {
+                    let at = text.len();
+                    let known = match tag.split_once(':').unwrap_or((tag, "")) {
+                        ("pause", value) => {
+                            markup.pauses.push((at, parse_pause(value, line)?));
+                            true
+                        }
+                        ("speed", value) => {
+                            let speed = value
+                                .trim()
+                                .parse()
+                                .map_err(|_| anyhow!("`{value}` is not a number"))
+                                .and_then(|speed| {
+                                    config::validate_speed(speed).map_err(anyhow::Error::msg)
+                                })
+                                .map_err(|err| anyhow!("line {line}: `{{{tag}}}`: {err}"))?;
+                            markup.speeds.push((at, Some(speed)));
+                            true
+                        }
+                        ("/speed", "") => {
+                            markup.speeds.push((at, None));
+                            true
+                        }
+                        ("color", value) => {
+                            let color = Color::from_str(value.trim()).map_err(|_| {
+                                anyhow!("line {line}: `{{{tag}}}`: `{value}` is not a colour")
+                            })?;
+                            colors.push((at, color, line));
+                            true
+                        }
+                        ("/color", "") => {
+                            let Some((start, color, _)) = colors.pop() else {
+                                bail!("line {line}: `{{/color}}` closes no `{{color:…}}`");
+                            };
+                            markup.styles.push(Styled {
+                                range: start..at,
+                                look: Look::Color(color),
+                            });
+                            true
+                        }
+                        _ => false,
+                    };
+                    if known {
+                        rest = tail;
+                        // A tag alone on its line takes the line with it.
+                        if text.is_empty() || text.ends_with('\n') {
+                            if let Some(tail) = rest.strip_prefix('\n') {
+                                rest = tail;
+                                line += 1;
+                            }
+                        }
+                        continue;
+                    }
+                }
+            }
+            '*' => {
+                let (marker, look, open) = if after.starts_with('*') {
+                    ("**", Look::Strong, &mut strong)
+                } else {
+                    ("*", Look::Emphasis, &mut emphasis)
+                };
+                let inner = &rest[marker.len()..];
+                let previous = text.chars().next_back();
+                let next = inner.chars().next();
+                match *open {
+                    Some(start) if previous.is_some_and(|ch| !ch.is_whitespace()) => {
+                        markup.styles.push(Styled {
+                            range: start..text.len(),
+                            look,
+                        });
+                        *open = None;
+                        rest = inner;
+                        continue;
+                    }
+                    // Only at the start of a word, so `2*3*4` keeps its asterisks.
+                    None if !previous.is_some_and(char::is_alphanumeric)
+                        && next.is_some_and(|ch| !ch.is_whitespace())
+                        && closes(inner, marker) =>
+                    {
+                        *open = Some(text.len());
+                        rest = inner;
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            '\n' => line += 1,
+            _ => {}
+        }
+        text.push(ch);
+        rest = after;
+    }
+
+    if let Some((_, _, opened)) = colors.pop() {
+        bail!("line {opened}: `{{color:…}}` is never closed with `{{/color}}`");
+    }
+    markup.styles.sort_by_key(|styled| styled.range.start);
+    Ok((text, markup))
+}
+
+/// Whether `marker`, opened just before `rest`, is closed later on the line.
+fn closes(rest: &str, marker: &str) -> bool {
+    let line = rest.split('\n').next().unwrap_or_default();
+    line.match_indices(marker).any(|(i, _)| {
+        line[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|ch| !ch.is_whitespace() && ch != '\\' && ch != '*')
+    })
+}
+
+/// `2s`, `1.5s` or `500ms`.
+fn parse_pause(value: &str, line: usize) -> Result<Duration> {
+    let value = value.trim();
+    let seconds = if let Some(ms) = value.strip_suffix("ms") {
+        ms.trim().parse::<f64>().map(|ms| ms / 1000.0)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        secs.trim().parse::<f64>()
+    } else {
+        bail!("line {line}: `{{pause:{value}}}` needs a unit, like `2s` or `500ms`");
+    };
+    match seconds {
+        Ok(seconds) if (0.0..=MAX_PAUSE.as_secs_f64()).contains(&seconds) => {
+            Ok(Duration::from_secs_f64(seconds))
+        }
+        _ => bail!(
+            "line {line}: `{{pause:{value}}}` must be between 0 and {}s",
+            MAX_PAUSE.as_secs()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_taken_out_of_the_text() {
+        let (text, markup) =
+            parse("Out of the night,{pause:1.5s} *black* {speed:80}as the pit{/speed}\n").unwrap();
+        assert_eq!(text, "Out of the night, black as the pit\n");
+        assert_eq!(markup.pauses, [(17, Duration::from_millis(1500))]);
+        assert_eq!(markup.speeds, [(24, Some(80)), (34, None)]);
+        assert_eq!(
+            markup.styles,
+            [Styled {
+                range: 18..23,
+                look: Look::Emphasis
+            }]
+        );
+        assert_eq!(markup.speed_at(30), Some(80));
+        assert_eq!(markup.speed_at(34), None);
+        assert_eq!(markup.pause_within(10, 17), Duration::from_millis(1500));
+        assert_eq!(markup.pause_within(17, 20), Duration::ZERO);
+
+        let (text, markup) = parse("**I** am *the* master").unwrap();
+        assert_eq!(text, "I am the master");
+        let looks: Vec<_> = markup
+            .styles
+            .iter()
+            .map(|s| (s.range.clone(), s.look))
+            .collect();
+        assert_eq!(looks, [(0..1, Look::Strong), (5..8, Look::Emphasis)]);
+    }
+
+    #[test]
+    fn colours_nest_and_tags_alone_on_a_line_vanish() {
+        let (text, markup) =
+            parse("{color:red}red {color:#00ff00}green{/color}{/color}\n{pause:500ms}\nnext\n")
+                .unwrap();
+        assert_eq!(text, "red green\nnext\n");
+        assert_eq!(markup.styles[0].range, 0..9);
+        assert_eq!(markup.styles[0].look, Look::Color(Color::Red));
+        assert_eq!(markup.styles[1].range, 4..9);
+        let style = markup.style_at(5, Style::default(), true);
+        assert_eq!(style.fg, Some(Color::Rgb(0, 0xff, 0)));
+        assert_eq!(markup.style_at(5, Style::default(), false).fg, None);
+        assert_eq!(markup.pauses, [(10, Duration::from_millis(500))]);
+    }
+
+    #[test]
+    fn literal_braces_and_asterisks_survive() {
+        let (text, markup) =
+            parse(r"\{pause:2s\} {not a tag} * * * 2*3 \*a\* a\\b Price is 2*3*4 dollars").unwrap();
+        assert_eq!(
+            text,
+            r"{pause:2s} {not a tag} * * * 2*3 *a* a\b Price is 2*3*4 dollars"
+        );
+        assert_eq!(markup, Markup::default());
+    }
+
+    #[test]
+    fn bad_tags_are_reported_with_their_line() {
+        let err = |source| parse(source).unwrap_err().to_string();
+        assert!(err("a\n{pause:2}").starts_with("line 2:"));
+        assert!(err("{pause:2h}").contains("needs a unit"));
+        assert!(err("{speed:0}").contains("speed must be between"));
+        assert!(err("{color:octarine}x{/color}").contains("not a colour"));
+        assert!(err("{color:red}x").contains("never closed"));
+        assert!(err("x{/color}").contains("closes no"));
+    }
+
+    #[test]
+    fn markup_can_start_part_way() {
+        let (text, markup) = parse("ab{speed:50}cd{pause:1s}e *fg*").unwrap();
+        assert_eq!(text, "abcde fg");
+        let rest = markup.starting_at(3);
+        assert_eq!(rest.speeds, [(0, Some(50))]);
+        assert_eq!(rest.pauses, [(1, Duration::from_secs(1))]);
+        assert_eq!(rest.styles[0].range, 3..5);
+    }
+}
@@ -0,0 +1,91 @@
+//! The `random` and `daily` commands: a writing picked by chance.
+//!
+//! Both draw from the same filtered, name-ordered rows as `list`. `random`
+//! seeds from the clock, `daily` from the date, so everyone with the same
+//! library sees the same writing all day. `--seed` replaces either for
+//! repeatable picks.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::listing::Row;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Days since 1970-01-01, in UTC.
+pub fn today() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_secs() / SECS_PER_DAY
+}
+
+/// A seed that differs from run to run.
+pub fn clock_seed() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_nanos() as u64 ^ u64::from(std::process::id())
+}
+
+/// SplitMix64: spreads neighbouring seeds, such as consecutive days, far
+/// apart.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The row `seed` lands on, or `None` when there are none.
+pub fn choose(rows: &[Row], seed: u64) -> Option<&Row> {
+    if rows.is_empty() {
+        return None;
+    }
+    let index = mix(seed) % rows.len() as u64;
+    rows.get(index as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unveilox::config::Settings;
+    use unveilox::library::{Entry, Source};
+    use unveilox::Writing;
+
+    fn rows(names: &[&str]) -> Vec<Row> {
+        names
+            .iter()
+            .map(|name| {
+                let entry = Entry {
+                    name: name.to_string(),
+                    source: Source::Bundled,
+                    shadows: Vec::new(),
+                };
+                let writing = Writing::parse(name, "words\n").unwrap();
+                Row::new(&entry, &writing, &Settings::default())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_same_seed_picks_the_same_writing() {
+        let rows = rows(&["if", "invictus", "ozymandias", "the_raven"]);
+        let picks: Vec<_> = [0, 1, 20_000, u64::MAX]
+            .into_iter()
+            .map(|seed| choose(&rows, seed).unwrap().name.as_str())
+            .collect();
+        assert_eq!(picks, ["the_raven", "invictus", "ozymandias", "if"]);
+        assert!(choose(&[], 7).is_none());
+    }
+
+    #[test]
+    fn consecutive_days_reach_every_writing() {
+        let rows = rows(&["if", "invictus", "ozymandias", "the_raven"]);
+        let mut seen: Vec<_> = (20_000..20_060)
+            .map(|day| choose(&rows, day).unwrap().name.clone())
+            .collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), rows.len());
+    }
+}
@@ -0,0 +1,98 @@
+//! Playlists: an ordered list of writings played back to back.
+//!
+//! A playlist file holds one writing name per line. Blank lines and lines
+//! starting with `#` are ignored.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// The names of the writings to play, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playlist {
+    entries: Vec<String>,
+}
+
+impl Playlist {
+    /// A playlist of `names`, which must not be empty.
+    pub fn new<I, S>(names: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let entries: Vec<String> = names
+            .into_iter()
+            .map(|name| name.as_ref().trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        if entries.is_empty() {
+            bail!("Playlist must contain at least one writing");
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Reads the one-name-a-line playlist format.
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::new(
+            text.lines()
+                .filter(|line| !line.trim_start().starts_with('#')),
+        )
+    }
+
+    /// Reads a playlist file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = crate::library::read_path(path)?;
+        Self::parse(&text).with_context(|| format!("in playlist {}", path.display()))
+    }
+
+    /// The writing names, in playing order.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+}
+
+/// Where playback should go after the entry at `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Next,
+    Previous,
+    Stop,
+}
+
+/// Index of the entry to play after `index`, or `None` once playback is over.
+/// Stepping back from the first entry replays it.
+pub(crate) fn advance(index: usize, len: usize, step: Step) -> Option<usize> {
+    match step {
+        Step::Next if index + 1 < len => Some(index + 1),
+        Step::Previous => Some(index.saturating_sub(1)),
+        Step::Next | Step::Stop => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        let playlist = Playlist::parse("# opening\ninvictus\n\n  if  \n# encore\n").unwrap();
+        assert_eq!(playlist.entries(), ["invictus", "if"]);
+    }
+
+    #[test]
+    fn empty_playlist_is_rejected() {
+        assert!(Playlist::parse("# nothing here\n\n").is_err());
+        assert!(Playlist::new(Vec::<String>::new()).is_err());
+    }
+
+    #[test]
+    fn advance_stays_within_bounds() {
+        assert_eq!(advance(0, 3, Step::Next), Some(1));
+        assert_eq!(advance(2, 3, Step::Next), None);
+        assert_eq!(advance(2, 3, Step::Previous), Some(1));
+        assert_eq!(advance(0, 3, Step::Previous), Some(0));
+        assert_eq!(advance(1, 3, Step::Stop), None);
+    }
+}
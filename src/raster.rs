@@ -0,0 +1,448 @@
+//! Animated GIF export: the film drawn frame by frame with a TrueType font.
+//!
+//! Like the typewriter, a frame only draws the graphemes revealed since the
+//! one before, unless the shot or the scrolling changed, and only the region
+//! that changed is stored. Steps closer together than browsers will show
+//! are merged.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use anyhow::{bail, Context, Result};
+use ratatui::style::Modifier;
+
+use crate::export::{self, Cell, Film, Shot};
+
+/// DejaVu Sans Mono, used when no font file is given.
+static DEFAULT_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+/// Shortest frame browsers play at its own length, in hundredths of a second.
+const MIN_FRAME: u64 = 2;
+/// Shades of each colour used to smooth the edges of glyphs.
+const SHADES: usize = 3;
+/// Slant of the italics drawn from an upright font, across per pixel up.
+const SLANT: f32 = 0.2;
+
+/// The font file at `path`, or the bundled one.
+pub fn load_font(path: Option<&Path>) -> Result<FontArc> {
+    match path {
+        Some(path) => {
+            let bytes =
+                fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+            FontArc::try_from_vec(bytes)
+                .with_context(|| format!("{} is not a TrueType or OpenType font", path.display()))
+        }
+        None => Ok(FontArc::try_from_slice(DEFAULT_FONT)?),
+    }
+}
+
+/// The GIF for `film`, set in `font` at `font_size` pixels.
+pub fn render(film: &Film, font: &FontArc, font_size: u16) -> Result<Vec<u8>> {
+    let mut canvas = Canvas::new(film, font, font_size)?;
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut pending: Option<(u64, Option<Rect>)> = None;
+    let mut drawn: Option<(usize, usize, usize)> = None;
+
+    for (index, shot) in film.shots.iter().enumerate() {
+        for step in &shot.steps {
+            let at = centis(step.at);
+            if let Some((start, dirty)) = pending.filter(|&(start, _)| at >= start + MIN_FRAME) {
+                pending = None;
+                canvas.push_frame(&mut frames, dirty, at - start);
+            }
+
+            let dirty = match drawn {
+                Some((shown, top, visible)) if shown == index && top == step.top => {
+                    canvas.draw(shot, step.top, visible..step.visible)
+                }
+                _ => {
+                    canvas.clear();
+                    canvas.draw(shot, step.top, 0..step.visible);
+                    Some(canvas.bounds())
+                }
+            };
+            drawn = Some((index, step.top, step.visible));
+            pending = Some(match pending {
+                Some((start, previous)) => (start, union(previous, dirty)),
+                None => (at, dirty),
+            });
+        }
+    }
+    if let Some((start, dirty)) = pending {
+        let delay = (centis(film.length) - start).max(MIN_FRAME);
+        canvas.push_frame(&mut frames, dirty, delay);
+    }
+
+    let mut gif = Vec::new();
+    let mut encoder = gif::Encoder::new(&mut gif, canvas.width, canvas.height, &canvas.palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for frame in &frames {
+        encoder.write_frame(&gif::Frame {
+            left: frame.rect.left,
+            top: frame.rect.top,
+            width: frame.rect.width,
+            height: frame.rect.height,
+            delay: u16::try_from(frame.delay).unwrap_or(u16::MAX),
+            dispose: gif::DisposalMethod::Keep,
+            buffer: Cow::Borrowed(&frame.pixels),
+            ..gif::Frame::default()
+        })?;
+    }
+    encoder.into_inner()?;
+    Ok(gif)
+}
+
+fn centis(duration: Duration) -> u64 {
+    (duration.as_millis() / 10) as u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    left: u16,
+    top: u16,
+    width: u16,
+    height: u16,
+}
+
+fn union(a: Option<Rect>, b: Option<Rect>) -> Option<Rect> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let left = a.left.min(b.left);
+            let top = a.top.min(b.top);
+            let right = (a.left + a.width).max(b.left + b.width);
+            let bottom = (a.top + a.height).max(b.top + b.height);
+            Some(Rect {
+                left,
+                top,
+                width: right - left,
+                height: bottom - top,
+            })
+        }
+        (rect, None) | (None, rect) => rect,
+    }
+}
+
+/// A stored frame: the region that changed and its new pixels.
+struct Frame {
+    rect: Rect,
+    pixels: Vec<u8>,
+    delay: u64,
+}
+
+/// A glyph's coverage, by pixel offset from the top left of its cell.
+type Bitmap = Vec<(i32, i32, f32)>;
+
+/// The screen in palette indices, plus what it takes to draw on it.
+struct Canvas<'a> {
+    font: &'a FontArc,
+    scale: PxScale,
+    ascent: f32,
+    cell: (usize, usize),
+    rows: usize,
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+    /// `r, g, b` for each index; 0 is the background.
+    palette: Vec<u8>,
+    /// First palette index of each ink's shades.
+    inks: HashMap<[u8; 3], usize>,
+    shades: usize,
+    foreground: [u8; 3],
+    background: [u8; 3],
+    glyphs: HashMap<(String, bool, bool), Bitmap>,
+}
+
+impl<'a> Canvas<'a> {
+    fn new(film: &Film, font: &'a FontArc, font_size: u16) -> Result<Self> {
+        let scale = PxScale::from(f32::from(font_size));
+        let scaled = font.as_scaled(scale);
+        let cell = (
+            scaled.h_advance(font.glyph_id('M')).ceil().max(1.0) as usize,
+            (scaled.ascent() - scaled.descent() + scaled.line_gap())
+                .ceil()
+                .max(1.0) as usize,
+        );
+        let width = cell.0 * usize::from(film.size.width);
+        let height = cell.1 * usize::from(film.size.height);
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            bail!("a {width}x{height} pixel GIF is too large; use a smaller --size or --font-size");
+        };
+
+        let mut canvas = Self {
+            font,
+            scale,
+            ascent: scaled.ascent(),
+            cell,
+            rows: usize::from(film.size.height),
+            width,
+            height,
+            pixels: vec![0; usize::from(width) * usize::from(height)],
+            palette: Vec::new(),
+            inks: HashMap::new(),
+            shades: SHADES,
+            foreground: film.foreground,
+            background: film.background,
+            glyphs: HashMap::new(),
+        };
+        canvas.build_palette(film);
+        Ok(canvas)
+    }
+
+    /// The background plus shades of every ink in `film`, with fewer shades
+    /// when they would not fit in 256 colours.
+    fn build_palette(&mut self, film: &Film) {
+        let mut inks: Vec<[u8; 3]> = Vec::new();
+        for cell in film.shots.iter().flat_map(|shot| &shot.cells) {
+            let ink = export::ink(cell.style, film.foreground, film.background);
+            if !inks.contains(&ink) {
+                inks.push(ink);
+            }
+        }
+        while self.shades > 1 && 1 + inks.len() * self.shades > 256 {
+            self.shades -= 1;
+        }
+        inks.truncate(255 / self.shades);
+
+        self.palette = self.background.to_vec();
+        for (index, ink) in inks.iter().enumerate() {
+            self.inks.insert(*ink, 1 + index * self.shades);
+            for shade in 1..=self.shades {
+                let amount = shade as f32 / self.shades as f32;
+                self.palette
+                    .extend(export::blend(self.background, *ink, amount));
+            }
+        }
+        if self.palette.len() < 6 {
+            self.palette.extend(self.foreground);
+        }
+    }
+
+    /// First palette index for `ink`, or for the closest one kept.
+    fn ink_index(&self, ink: [u8; 3]) -> usize {
+        if let Some(&index) = self.inks.get(&ink) {
+            return index;
+        }
+        let distance = |other: &[u8; 3]| -> i32 {
+            (0..3)
+                .map(|i| (i32::from(ink[i]) - i32::from(other[i])).pow(2))
+                .sum()
+        };
+        self.inks
+            .iter()
+            .min_by_key(|(other, &index)| (distance(other), index))
+            .map_or(0, |(_, &index)| index)
+    }
+
+    fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect {
+            left: 0,
+            top: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Draws the cells of `shot` revealed within `offsets` that are in view
+    /// below row `top`, returning the region touched.
+    fn draw(&mut self, shot: &Shot, top: usize, offsets: Range<usize>) -> Option<Rect> {
+        let mut dirty = None;
+        let in_view = top..top + self.rows;
+        for cell in &shot.cells {
+            if offsets.contains(&cell.offset) && in_view.contains(&cell.row) {
+                dirty = union(dirty, self.draw_cell(cell, cell.row - top));
+            }
+        }
+        dirty
+    }
+
+    fn draw_cell(&mut self, cell: &Cell, row: usize) -> Option<Rect> {
+        if cell.grapheme.trim().is_empty() {
+            return None;
+        }
+        let modifiers = cell.style.add_modifier;
+        let key = (
+            cell.grapheme.clone(),
+            modifiers.contains(Modifier::BOLD),
+            modifiers.contains(Modifier::ITALIC),
+        );
+        if !self.glyphs.contains_key(&key) {
+            let bitmap = self.rasterize(&key.0, key.1, key.2);
+            self.glyphs.insert(key.clone(), bitmap);
+        }
+        let ink = export::ink(cell.style, self.foreground, self.background);
+        let first = self.ink_index(ink);
+
+        let left = (cell.col * self.cell.0) as i32;
+        let top = (row * self.cell.1) as i32;
+        let (width, height) = (i32::from(self.width), i32::from(self.height));
+        let mut touched: Option<(i32, i32, i32, i32)> = None;
+        for &(dx, dy, coverage) in &self.glyphs[&key] {
+            let (x, y) = (left + dx, top + dy);
+            let shade = (coverage * self.shades as f32).round() as usize;
+            if shade == 0 || !(0..width).contains(&x) || !(0..height).contains(&y) {
+                continue;
+            }
+            self.pixels[(y * width + x) as usize] = (first + shade.min(self.shades) - 1) as u8;
+            touched = Some(match touched {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+        touched.map(|(x0, y0, x1, y1)| Rect {
+            left: x0 as u16,
+            top: y0 as u16,
+            width: (x1 - x0 + 1) as u16,
+            height: (y1 - y0 + 1) as u16,
+        })
+    }
+
+    /// Coverage of `grapheme`, emboldened and slanted as asked. Every
+    /// character of the cluster is drawn at the cell's origin, so combining
+    /// marks land on the letter they follow.
+    fn rasterize(&self, grapheme: &str, bold: bool, italic: bool) -> Bitmap {
+        let mut coverage: HashMap<(i32, i32), f32> = HashMap::new();
+        for ch in grapheme.chars() {
+            let glyph = self
+                .font
+                .glyph_id(ch)
+                .with_scale_and_position(self.scale, point(0.0, self.ascent));
+            let Some(outline) = self.font.outline_glyph(glyph) else {
+                continue;
+            };
+
+            let bounds = outline.px_bounds();
+            outline.draw(|x, y, c| {
+                let y = bounds.min.y as i32 + y as i32;
+                let mut x = bounds.min.x as i32 + x as i32;
+                if italic {
+                    x += ((self.ascent - y as f32) * SLANT).round() as i32;
+                }
+                let strokes: &[i32] = if bold { &[0, 1] } else { &[0] };
+                for stroke in strokes {
+                    let pixel = coverage.entry((x + stroke, y)).or_default();
+                    *pixel = pixel.max(c);
+                }
+            });
+        }
+        let mut bitmap: Bitmap = coverage.into_iter().map(|((x, y), c)| (x, y, c)).collect();
+        bitmap.sort_by_key(|&(x, y, _)| (y, x));
+        bitmap
+    }
+
+    /// Adds the region `dirty` as a frame lasting `delay`. An unchanged
+    /// screen lengthens the frame before it instead.
+    fn push_frame(&self, frames: &mut Vec<Frame>, dirty: Option<Rect>, delay: u64) {
+        if let (None, Some(previous)) = (dirty, frames.last_mut()) {
+            previous.delay += delay;
+            return;
+        }
+        let rect = dirty.unwrap_or(self.bounds());
+        let width = usize::from(self.width);
+        let mut pixels = Vec::with_capacity(usize::from(rect.width) * usize::from(rect.height));
+        for y in rect.top..rect.top + rect.height {
+            let start = usize::from(y) * width + usize::from(rect.left);
+            pixels.extend_from_slice(&self.pixels[start..start + usize::from(rect.width)]);
+        }
+        frames.push(Frame {
+            rect,
+            pixels,
+            delay,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{plan, Timing};
+    use crate::record::Size;
+    use crate::reveal::{Pauses, Unit};
+    use crate::theme::Theme;
+    use crate::writing::Writing;
+
+    fn decode(gif: &[u8]) -> (u16, u16, Vec<u16>) {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(gif).unwrap();
+        let size = (decoder.width(), decoder.height());
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        (size.0, size.1, delays)
+    }
+
+    #[test]
+    fn frames_follow_the_reveal_and_merge_short_steps() {
+        let writing = Writing::parse("x", "---\ntitle: X\n---\nab *c*\n").unwrap();
+        let size = Size {
+            width: 20,
+            height: 4,
+        };
+        let mut timing = Timing {
+            unit: Unit::Char,
+            speed_ms: 100,
+            pauses: Pauses::default(),
+            card: Duration::from_secs(3),
+            tail: Duration::from_secs(2),
+        };
+        let font = load_font(None).unwrap();
+        let film = plan(&writing, &Theme::default(), &timing, size, None);
+        let (width, height, delays) = decode(&render(&film, &font, 16).unwrap());
+        let cell = font.as_scaled(16.0).h_advance(font.glyph_id('M')).ceil() as u16;
+        assert_eq!(width, 20 * cell);
+        assert!(height >= 4 * 16);
+        // The card, then a, b and c; the space and newline draw nothing.
+        assert_eq!(delays, [300, 10, 20, 220]);
+        assert_eq!(
+            delays.iter().map(|&d| u64::from(d)).sum::<u64>(),
+            centis(film.length)
+        );
+
+        timing.speed_ms = 5;
+        let film = plan(&writing, &Theme::default(), &timing, size, None);
+        let (_, _, delays) = decode(&render(&film, &font, 16).unwrap());
+        assert!(delays.iter().all(|&delay| delay >= 2), "{delays:?}");
+    }
+
+    #[test]
+    fn combining_marks_are_drawn_over_their_letter() {
+        let writing = Writing::parse("x", "e\n").unwrap();
+        let size = Size {
+            width: 4,
+            height: 2,
+        };
+        let timing = Timing {
+            unit: Unit::Char,
+            speed_ms: 100,
+            pauses: Pauses::default(),
+            card: Duration::ZERO,
+            tail: Duration::ZERO,
+        };
+        let font = load_font(None).unwrap();
+        let film = plan(&writing, &Theme::default(), &timing, size, None);
+        let canvas = Canvas::new(&film, &font, 16).unwrap();
+
+        let plain = canvas.rasterize("e", false, false);
+        let accented = canvas.rasterize("e\u{301}", false, false);
+        assert!(accented.len() > plain.len());
+        let top = |bitmap: &Bitmap| bitmap.iter().map(|&(_, y, _)| y).min().unwrap();
+        assert!(top(&accented) < top(&plain));
+        let right = |bitmap: &Bitmap| bitmap.iter().map(|&(x, ..)| x).max().unwrap();
+        assert!(right(&accented) < canvas.cell.0 as i32);
+    }
+
+    #[test]
+    fn missing_fonts_are_reported() {
+        let err = load_font(Some(Path::new("/no/such/font.ttf"))).unwrap_err();
+        assert!(err.to_string().contains("failed to read"));
+    }
+}
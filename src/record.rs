@@ -0,0 +1,206 @@
+//! `--record`: a reveal written to an [asciicast v2] file instead of played.
+//!
+//! The reveal runs on a virtual clock against an in-memory screen of a fixed
+//! size, so no terminal is needed and the same writing and settings always
+//! give the same file. Each event carries exactly the bytes the terminal
+//! renderers would have written at that moment.
+//!
+//! [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+/// Width and height of the recorded screen, in cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            height: 24,
+        }
+    }
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    /// `COLSxROWS`, such as `80x24`.
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("`{raw}` is not a size like 80x24");
+        let (width, height) = raw.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+        let cells = |side: &str| side.trim().parse::<u16>().ok().filter(|&n| n >= 2);
+        match (cells(width), cells(height)) {
+            (Some(width), Some(height)) => Ok(Self { width, height }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Where to record and on what screen.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub path: PathBuf,
+    pub size: Size,
+}
+
+/// Output shared between a [`Recording`] and whatever draws into it.
+#[derive(Debug, Clone, Default)]
+pub struct Tape(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Tape {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Output events stamped with the virtual time they were written at.
+#[derive(Debug)]
+pub struct Recording {
+    size: Size,
+    title: String,
+    clock: Duration,
+    events: Vec<(Duration, String)>,
+    tape: Tape,
+}
+
+impl Recording {
+    pub fn new(size: Size, title: &str) -> Self {
+        Self {
+            size,
+            title: title.to_string(),
+            clock: Duration::ZERO,
+            events: Vec::new(),
+            tape: Tape::default(),
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// A handle to draw on; what is written to it goes into the next frame.
+    pub fn tape(&self) -> Tape {
+        self.tape.clone()
+    }
+
+    /// Stamps everything written since the last frame with the current time.
+    pub fn frame(&mut self) {
+        let bytes = std::mem::take(&mut *self.tape.0.borrow_mut());
+        if !bytes.is_empty() {
+            let output = String::from_utf8_lossy(&bytes).into_owned();
+            self.events.push((self.clock, output));
+        }
+    }
+
+    /// Moves the clock on by `duration`.
+    pub fn wait(&mut self, duration: Duration) {
+        self.clock += duration;
+    }
+
+    /// Ends the recording at the current time, so players hold the last
+    /// frame until then.
+    pub fn finish(&mut self) {
+        self.frame();
+        self.events.push((self.clock, String::new()));
+    }
+
+    /// The asciicast: a header line, then one `[time, "o", output]` line per
+    /// event.
+    pub fn write_to(&self, out: &mut impl Write) -> Result<()> {
+        let header = json!({
+            "version": 2,
+            "width": self.size.width,
+            "height": self.size.height,
+            "title": self.title,
+            "env": { "TERM": "xterm-256color" },
+        });
+        writeln!(out, "{header}")?;
+        for (at, output) in &self.events {
+            let seconds = at.as_micros() as f64 / 1_000_000.0;
+            writeln!(out, "{}", json!([seconds, "o", output]))?;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut cast = Vec::new();
+        self.write_to(&mut cast)?;
+        fs::write(path, cast).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_parse_as_cols_by_rows() {
+        assert_eq!(
+            "100x30".parse::<Size>(),
+            Ok(Size {
+                width: 100,
+                height: 30
+            })
+        );
+        assert_eq!(Size::default().to_string(), "80x24");
+        assert!("80".parse::<Size>().is_err());
+        assert!("0x24".parse::<Size>().is_err());
+        assert!("wide x tall".parse::<Size>().is_err());
+    }
+
+    #[test]
+    fn frames_carry_the_output_and_time() {
+        let mut recording = Recording::new(Size::default(), "Invictus");
+        let mut tape = recording.tape();
+        write!(tape, "Out").unwrap();
+        recording.frame();
+        recording.wait(Duration::from_millis(250));
+        recording.frame();
+        write!(tape, " of\r\n\"the\"").unwrap();
+        recording.frame();
+        recording.wait(Duration::from_secs(2));
+        recording.finish();
+
+        let mut cast = Vec::new();
+        recording.write_to(&mut cast).unwrap();
+        let cast = String::from_utf8(cast).unwrap();
+        let lines: Vec<_> = cast.lines().collect();
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 80);
+        assert_eq!(header["title"], "Invictus");
+        assert_eq!(
+            lines[1..],
+            [
+                r#"[0.0,"o","Out"]"#,
+                r#"[0.25,"o"," of\r\n\"the\""]"#,
+                r#"[2.25,"o",""]"#,
+            ]
+        );
+    }
+}
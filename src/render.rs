@@ -0,0 +1,634 @@
+//! Renderers: what puts a reveal in front of the reader as it progresses.
+//!
+//! [`Typewriter`] prints each newly revealed grapheme in place, [`Tui`]
+//! redraws a bordered ratatui frame, and [`Plain`] streams the bare text.
+//! Each draws to any [`Write`], so the same code serves the terminal and
+//! `--record`. [`Memory`] keeps the frames as text for tests.
+
+use std::io::Write;
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::{
+    cursor, execute, queue,
+    style::{self, Stylize},
+    terminal::{self, ClearType},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Paragraph},
+    Terminal, TerminalOptions, Viewport as TuiViewport,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::cinema::Sequence;
+use crate::clock::{Clock, VirtualClock};
+use crate::theme::{self, Theme};
+use crate::writing::Writing;
+
+/// Puts a reveal in front of the reader. The engine's scheduler decides
+/// what to show and calls these in order: [`begin`](Self::begin) once,
+/// then for every frame [`emit`](Self::emit) and
+/// [`newline`](Self::newline), with a [`clear`](Self::clear) first whenever
+/// it starts over, ended by [`present`](Self::present), and
+/// [`finish`](Self::finish) at the end.
+///
+/// # Examples
+///
+/// A renderer keeping a transcript, played through without a terminal:
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use ratatui::style::Style;
+/// use unveilox::{Renderer, RevealEngine, Writing};
+///
+/// struct Transcript(String);
+///
+/// impl Renderer for Transcript {
+///     fn emit(&mut self, grapheme: &str, _: Style) -> anyhow::Result<()> {
+///         self.0.push_str(grapheme);
+///         Ok(())
+///     }
+///
+///     fn newline(&mut self) -> anyhow::Result<()> {
+///         self.0.push('\n');
+///         Ok(())
+///     }
+/// }
+///
+/// let writing = Writing::parse("note", "Hello, world\n")?;
+/// let mut transcript = Transcript(String::new());
+/// let mut length = Duration::ZERO;
+/// RevealEngine::default().play_offline(&writing, &mut transcript, |pause| length += pause)?;
+/// assert_eq!(transcript.0, "Hello, world\n");
+/// assert!(length > Duration::ZERO);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub trait Renderer {
+    /// The columns and rows the text is laid out in, asked before every
+    /// frame. `None`, the default, is a stream that is never wrapped,
+    /// scrolled or cleared.
+    fn area(&mut self) -> Result<Option<(u16, u16)>> {
+        Ok(None)
+    }
+
+    /// Sets the screen up before the first frame.
+    fn begin(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Shows one grapheme after the last one, in `style`.
+    fn emit(&mut self, grapheme: &str, style: Style) -> Result<()>;
+
+    /// Moves on to the start of the next row.
+    fn newline(&mut self) -> Result<()>;
+
+    /// Empties the area; what follows is emitted from its first row.
+    fn clear(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Ends a frame with the playback `status` line.
+    fn present(&mut self, _status: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once the reveal is over.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The typewriter: each grapheme printed in place, the cursor only moved
+/// when the next one is not where the last one left it.
+pub struct Typewriter<'a, W: Write> {
+    out: W,
+    /// The screen size, or `None` to follow the terminal's.
+    fixed: Option<(u16, u16)>,
+    theme: &'a Theme,
+    size: (u16, u16),
+    /// Where the next grapheme goes, and where the terminal's cursor is if
+    /// known.
+    cursor: (u16, u16),
+    at: Option<(u16, u16)>,
+}
+
+impl<'a, W: Write> Typewriter<'a, W> {
+    /// A typewriter printing to `out`, a terminal whose size it asks for on
+    /// every frame.
+    pub fn new(out: W, theme: &'a Theme) -> Self {
+        Self {
+            out,
+            fixed: None,
+            theme,
+            size: (0, 0),
+            cursor: (0, 0),
+            at: None,
+        }
+    }
+
+    /// Draws on a screen of `width` by `height` cells instead.
+    pub fn with_size(mut self, (width, height): (u16, u16)) -> Self {
+        self.fixed = Some((width, height));
+        self
+    }
+}
+
+impl<W: Write> Renderer for Typewriter<'_, W> {
+    fn area(&mut self) -> Result<Option<(u16, u16)>> {
+        self.size = match self.fixed {
+            Some(size) => size,
+            None => terminal::size()?,
+        };
+        // The bottom row is kept for the status line.
+        let (width, height) = self.size;
+        Ok(Some((width, height.saturating_sub(1).max(1))))
+    }
+
+    fn begin(&mut self) -> Result<()> {
+        paint_background(&mut self.out, self.theme)?;
+        queue!(
+            self.out,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        Ok(())
+    }
+
+    fn emit(&mut self, grapheme: &str, style: Style) -> Result<()> {
+        let (col, row) = self.cursor;
+        if self.at != Some(self.cursor) {
+            queue!(self.out, cursor::MoveTo(col, row))?;
+        }
+        queue!(
+            self.out,
+            style::PrintStyledContent(theme::content_style(style).apply(grapheme))
+        )?;
+        self.cursor = (col.saturating_add(grapheme.width() as u16), row);
+        self.at = Some(self.cursor);
+        Ok(())
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        self.cursor = (0, self.cursor.1.saturating_add(1));
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        paint_background(&mut self.out, self.theme)?;
+        queue!(self.out, terminal::Clear(ClearType::All))?;
+        self.cursor = (0, 0);
+        self.at = None;
+        Ok(())
+    }
+
+    fn present(&mut self, status: &str) -> Result<()> {
+        let (width, height) = self.size;
+        // Clipped short of the last column so the terminal never scrolls.
+        let status: String = status
+            .chars()
+            .take(usize::from(width.saturating_sub(1)))
+            .collect();
+        queue!(
+            self.out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            terminal::Clear(ClearType::CurrentLine),
+            style::PrintStyledContent(status.dark_grey())
+        )?;
+        self.at = None;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+/// The TUI: a bordered frame with a status line, redrawn by ratatui from the
+/// lines emitted so far onto any ratatui backend.
+pub struct Tui<'a, B: Backend> {
+    terminal: Terminal<B>,
+    /// The screen size, or `None` to follow the terminal's.
+    fixed: Option<(u16, u16)>,
+    theme: &'a Theme,
+    /// The title on the border, the writing's once one is given.
+    title: String,
+    lines: Vec<Line<'static>>,
+}
+
+impl<'a, W: Write> Tui<'a, CrosstermBackend<W>> {
+    /// A TUI drawing to `out`, a terminal it follows the size of.
+    pub fn new(out: W, theme: &'a Theme) -> Result<Self> {
+        Self::with_backend(CrosstermBackend::new(out), theme)
+    }
+
+    /// A TUI drawing on a screen of `width` by `height` cells, which is
+    /// never asked for its size.
+    pub fn with_size(out: W, (width, height): (u16, u16), theme: &'a Theme) -> Result<Self> {
+        let options = TerminalOptions {
+            viewport: TuiViewport::Fixed(Rect::new(0, 0, width, height)),
+        };
+        Ok(Self {
+            terminal: Terminal::with_options(CrosstermBackend::new(out), options)?,
+            fixed: Some((width, height)),
+            theme,
+            title: String::new(),
+            lines: vec![Line::default()],
+        })
+    }
+}
+
+impl<'a, B: Backend> Tui<'a, B> {
+    /// A TUI drawing on `backend`, e.g. a ratatui `TestBackend`, at the
+    /// size it reports.
+    pub fn with_backend(backend: B, theme: &'a Theme) -> Result<Self> {
+        Ok(Self {
+            terminal: Terminal::new(backend)?,
+            fixed: None,
+            theme,
+            title: String::new(),
+            lines: vec![Line::default()],
+        })
+    }
+
+    /// The same TUI with `title` on the border around the text.
+    pub fn titled(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn backend(&self) -> &B {
+        self.terminal.backend()
+    }
+
+    /// The whole screen, borders and status line included.
+    pub fn screen(&mut self) -> Result<Rect> {
+        Ok(match self.fixed {
+            Some((width, height)) => Rect::new(0, 0, width, height),
+            None => self.terminal.size()?,
+        })
+    }
+
+    /// Draws `frame` of a title sequence or the credits over the screen.
+    pub(crate) fn show(&mut self, sequence: &Sequence, frame: usize) -> Result<()> {
+        self.terminal.draw(|f| sequence.draw(f, frame))?;
+        Ok(())
+    }
+}
+
+/// The bordered text area and the status line below it.
+fn tui_layout(size: Rect, title: &str) -> (Block<'_>, Rect, Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+        .split(size);
+    let block = Block::default().borders(Borders::ALL).title(title);
+    (block, chunks[0], chunks[1])
+}
+
+impl<B: Backend> Renderer for Tui<'_, B> {
+    fn area(&mut self) -> Result<Option<(u16, u16)>> {
+        let (block, text, _) = tui_layout(self.screen()?, &self.title);
+        let inner = block.inner(text);
+        Ok(Some((inner.width, inner.height)))
+    }
+
+    fn begin(&mut self) -> Result<()> {
+        let backend = self.terminal.backend_mut();
+        backend.clear()?;
+        backend.set_cursor(0, 0)?;
+        self.terminal.hide_cursor()?;
+        Ok(())
+    }
+
+    fn emit(&mut self, grapheme: &str, style: Style) -> Result<()> {
+        let line = self.lines.last_mut().expect("there is always a line");
+        match line.spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(grapheme),
+            _ => line.spans.push(Span::styled(grapheme.to_string(), style)),
+        }
+        Ok(())
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        self.lines.push(Line::default());
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.lines = vec![Line::default()];
+        Ok(())
+    }
+
+    fn present(&mut self, status: &str) -> Result<()> {
+        let base = self.theme.base_style();
+        let text = Text::from(self.lines.clone());
+        let title = &self.title;
+        self.terminal.draw(|f| {
+            let (block, area, status_area) = tui_layout(f.size(), title);
+            let paragraph = Paragraph::new(text)
+                .block(block)
+                .alignment(Alignment::Left)
+                .style(base);
+            let status = Paragraph::new(status).style(base.add_modifier(Modifier::DIM));
+            f.render_widget(paragraph, area);
+            f.render_widget(status, status_area);
+        })?;
+        Ok(())
+    }
+}
+
+/// The bare text, written as it is revealed.
+pub struct Plain<W: Write> {
+    out: W,
+    line_ending: &'static str,
+}
+
+impl<W: Write> Plain<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            line_ending: "\n",
+        }
+    }
+
+    /// Plain text for a terminal in raw mode, or a recording of one, where a
+    /// line break needs a carriage return too.
+    pub fn raw(out: W) -> Self {
+        Self {
+            out,
+            line_ending: "\r\n",
+        }
+    }
+}
+
+impl<W: Write> Renderer for Plain<W> {
+    fn emit(&mut self, grapheme: &str, _: Style) -> Result<()> {
+        self.out.write_all(grapheme.as_bytes())?;
+        Ok(())
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        self.out.write_all(self.line_ending.as_bytes())?;
+        Ok(())
+    }
+
+    fn present(&mut self, _: &str) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+/// A frame as [`Memory`] saw it presented.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// When it was presented, by the clock given to
+    /// [`Memory::with_clock`], or zero.
+    pub at: Duration,
+    pub lines: Vec<String>,
+    pub status: String,
+}
+
+/// Keeps the screen as text, and a [`Snapshot`] of every frame, for tests.
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    /// The area it reports, or `None` to take the text as a stream.
+    pub area: Option<(u16, u16)>,
+    /// The rows emitted since the last clear.
+    pub lines: Vec<String>,
+    pub frames: Vec<Snapshot>,
+    pub clears: usize,
+    pub begun: bool,
+    pub finished: bool,
+    clock: Option<VirtualClock>,
+}
+
+impl Memory {
+    /// A stream, never wrapped or cleared.
+    pub fn new() -> Self {
+        Self {
+            lines: vec![String::new()],
+            ..Self::default()
+        }
+    }
+
+    /// A screen whose text area is `width` by `height` cells.
+    pub fn with_size((width, height): (u16, u16)) -> Self {
+        Self {
+            area: Some((width, height)),
+            ..Self::new()
+        }
+    }
+
+    /// Stamps each frame with the time on `clock`.
+    pub fn with_clock(mut self, clock: &VirtualClock) -> Self {
+        self.clock = Some(clock.clone());
+        self
+    }
+}
+
+impl Renderer for Memory {
+    fn area(&mut self) -> Result<Option<(u16, u16)>> {
+        Ok(self.area)
+    }
+
+    fn begin(&mut self) -> Result<()> {
+        self.begun = true;
+        Ok(())
+    }
+
+    fn emit(&mut self, grapheme: &str, _: Style) -> Result<()> {
+        match self.lines.last_mut() {
+            Some(line) => line.push_str(grapheme),
+            None => self.lines.push(grapheme.to_string()),
+        }
+        Ok(())
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        self.lines.push(String::new());
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.lines = vec![String::new()];
+        self.clears += 1;
+        Ok(())
+    }
+
+    fn present(&mut self, status: &str) -> Result<()> {
+        self.frames.push(Snapshot {
+            at: self
+                .clock
+                .as_ref()
+                .map_or(Duration::ZERO, |clock| clock.now()),
+            lines: self.lines.clone(),
+            status: status.to_string(),
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.finished = true;
+        Ok(())
+    }
+}
+
+/// Title and byline as plain lines, followed by a blank one.
+pub(crate) fn plain_heading(writing: &Writing) -> String {
+    match writing.byline() {
+        Some(byline) => format!("{}\nBy {byline}\n", writing.title()),
+        None => format!("{}\n", writing.title()),
+    }
+}
+
+/// Fills the screen with the theme's background on the next clear.
+pub(crate) fn paint_background(out: &mut impl Write, theme: &Theme) -> Result<()> {
+    if let Some(background) = theme.background {
+        execute!(out, style::SetBackgroundColor(background.into()))?;
+    }
+    Ok(())
+}
+
+/// Draws a title card on a cleared screen of the given size: the writing's
+/// title and byline, plus its place in a playlist when there is a `counter`.
+pub(crate) fn paint_title_card(
+    out: &mut impl Write,
+    (width, height): (u16, u16),
+    writing: &Writing,
+    counter: Option<&str>,
+    theme: &Theme,
+) -> Result<()> {
+    let title_style = theme::content_style(theme.base_style());
+    let title = writing.title();
+    let middle = height / 2;
+    queue!(
+        out,
+        cursor::Hide,
+        cursor::MoveTo(centered_column(width, title), middle.saturating_sub(1)),
+        style::PrintStyledContent(title_style.apply(title).bold()),
+    )?;
+    if let Some(byline) = writing.byline() {
+        queue!(
+            out,
+            cursor::MoveTo(centered_column(width, &byline), middle),
+            style::PrintStyledContent(title_style.apply(byline.as_str()).italic()),
+        )?;
+    }
+    if let Some(counter) = counter {
+        queue!(
+            out,
+            cursor::MoveTo(centered_column(width, counter), middle.saturating_add(2)),
+            style::PrintStyledContent(counter.dark_grey()),
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn centered_column(width: u16, text: &str) -> u16 {
+    let len = u16::try_from(text.width()).unwrap_or(u16::MAX);
+    width.saturating_sub(len) / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+    use crate::controls::Control;
+    use crate::engine::{Playback, RevealEngine};
+    use crate::reveal::Unit;
+    use crate::scheduler::Scheduler;
+    use crate::viewport::Scroll;
+    use ratatui::backend::TestBackend;
+
+    fn screen(backend: &TestBackend) -> Vec<String> {
+        let buffer = backend.buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tui_snapshots_follow_the_reveal() {
+        let mut settings = Settings::default();
+        settings.unit.value = Unit::Line;
+        let engine = RevealEngine::new(settings, Theme::default());
+        let playback = Playback::new(&engine);
+        let writing = Writing::parse(
+            "invictus",
+            "Out of the night\nthat covers me,\nBlack as the pit\nfrom pole to pole,\n",
+        )
+        .unwrap();
+        let mut scheduler = Scheduler::new(&writing, &playback);
+        let mut tui = Tui::with_backend(TestBackend::new(34, 6), engine.theme())
+            .unwrap()
+            .titled(writing.title());
+
+        tui.begin().unwrap();
+        scheduler.draw(&mut tui).unwrap();
+        scheduler.advance();
+        scheduler.draw(&mut tui).unwrap();
+        assert_eq!(
+            screen(tui.backend()),
+            [
+                "┌invictus────────────────────────┐",
+                "│Out of the night                │",
+                "│                                │",
+                "│                                │",
+                "└────────────────────────────────┘",
+                "▶ 25 ms/char · space pause · +/- s",
+            ]
+        );
+
+        while scheduler.advance().is_some() {
+            scheduler.draw(&mut tui).unwrap();
+        }
+        assert_eq!(
+            screen(tui.backend()),
+            [
+                "┌invictus────────────────────────┐",
+                "│that covers me,                 │",
+                "│Black as the pit                │",
+                "│from pole to pole,              │",
+                "└────────────────────────────────┘",
+                "■ done · esc quit                 ",
+            ]
+        );
+
+        scheduler.scroll(Scroll::Top);
+        scheduler.draw(&mut tui).unwrap();
+        assert_eq!(
+            screen(tui.backend())[1],
+            "│Out of the night                │"
+        );
+    }
+
+    #[test]
+    fn tui_wraps_to_its_inner_width() {
+        let engine = RevealEngine::default();
+        let playback = Playback::new(&engine);
+        let writing = Writing::parse("x", "I am the master of my fate\n").unwrap();
+        let mut scheduler = Scheduler::new(&writing, &playback);
+        let mut tui = Tui::with_backend(TestBackend::new(16, 6), engine.theme()).unwrap();
+
+        scheduler.control(Control::RevealAll);
+        scheduler.draw(&mut tui).unwrap();
+        assert_eq!(
+            &screen(tui.backend())[1..4],
+            ["│I am the      │", "│master of my  │", "│fate          │"]
+        );
+    }
+}
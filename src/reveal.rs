@@ -0,0 +1,522 @@
+//! Splitting a text into the units it is revealed in.
+//!
+//! `--speed` stays "milliseconds per character" whatever the unit: a word of
+//! five letters is held five times as long as a single character would be, so
+//! changing the unit changes the rhythm but not the overall pace.
+//!
+//! A character here is a grapheme cluster, so an accent typed as a combining
+//! mark or an emoji built from several code points appears in one step.
+//!
+//! On top of that pace come [`Pauses`]: a breath after commas, full stops and
+//! dashes, at line ends and between stanzas, the way a poem is recited.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::{MAX_SPEED, MIN_SPEED};
+use crate::markup::Markup;
+
+/// How much of the text each step of a reveal shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    #[default]
+    Char,
+    Word,
+    Line,
+    Stanza,
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "char" | "character" => Ok(Unit::Char),
+            "word" => Ok(Unit::Word),
+            "line" => Ok(Unit::Line),
+            "stanza" => Ok(Unit::Stanza),
+            other => Err(format!(
+                "unknown reveal unit `{other}` (expected char, word, line or stanza)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Unit::Char => "char",
+            Unit::Word => "word",
+            Unit::Line => "line",
+            Unit::Stanza => "stanza",
+        })
+    }
+}
+
+/// Extra milliseconds held after a unit, at the configured speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pauses {
+    /// After `,`, `;` and `:`.
+    pub comma: u64,
+    /// After `.`, `!`, `?` and `…`.
+    pub period: u64,
+    /// After an em or en dash.
+    pub dash: u64,
+    /// At the end of a line.
+    pub line: u64,
+    /// Between stanzas, instead of the line pause.
+    pub stanza: u64,
+}
+
+impl Pauses {
+    /// The default cadence.
+    pub const RECITED: Self = Self {
+        comma: 120,
+        period: 300,
+        dash: 200,
+        line: 250,
+        stanza: 800,
+    };
+
+    /// The pause after `unit`, which ends `before`; `stanza_break` says
+    /// whether a new stanza follows. The longer of a punctuation and a line
+    /// or stanza pause applies.
+    fn after(&self, before: &str, unit: &str, stanza_break: bool) -> u64 {
+        let boundary = if stanza_break {
+            self.stanza
+        } else if let Some(line) = before.strip_suffix('\n') {
+            // Blank lines between stanzas don't pause on their own.
+            let last_line = &line[line.rfind('\n').map_or(0, |i| i + 1)..];
+            if last_line.trim().is_empty() {
+                0
+            } else {
+                self.line
+            }
+        } else {
+            0
+        };
+
+        // Only punctuation in this unit counts, so the space after a comma
+        // doesn't pause a second time. Closing quotes and brackets are skipped.
+        let mark = unit
+            .trim_end_matches(|ch: char| ch.is_whitespace() || CLOSERS.contains(&ch))
+            .chars()
+            .next_back();
+        let punctuation = match mark {
+            Some(',' | ';' | ':') => self.comma,
+            Some('.' | '!' | '?' | '…') => self.period,
+            Some('—' | '–') => self.dash,
+            _ => 0,
+        };
+
+        boundary.max(punctuation)
+    }
+}
+
+const CLOSERS: [char; 8] = ['"', '\'', '”', '’', '»', ')', ']', '*'];
+
+/// Consecutive slices of `text`, one per unit, that concatenate back to it.
+///
+/// Whitespace after a word, line or stanza belongs to it, so a unit ends just
+/// before the next one starts.
+pub fn segments(text: &str, unit: Unit) -> Vec<&str> {
+    let mut ends: Vec<usize> = match unit {
+        Unit::Char => text
+            .grapheme_indices(true)
+            .skip(1)
+            .map(|(i, _)| i)
+            .collect(),
+        Unit::Word => text
+            .char_indices()
+            .zip(text.chars().skip(1))
+            .filter(|((_, ch), next)| ch.is_whitespace() && !next.is_whitespace())
+            .map(|((i, ch), _)| i + ch.len_utf8())
+            .collect(),
+        Unit::Line => text.match_indices('\n').map(|(i, _)| i + 1).collect(),
+        Unit::Stanza => {
+            let mut ends = Vec::new();
+            let mut offset = 0;
+            let mut blank_run = false;
+            for line in text.split_inclusive('\n') {
+                let blank = line.trim().is_empty();
+                if blank_run && !blank {
+                    ends.push(offset);
+                }
+                blank_run = blank;
+                offset += line.len();
+            }
+            ends
+        }
+    };
+    ends.retain(|&end| end < text.len());
+    ends.push(text.len());
+
+    let mut segments = Vec::with_capacity(ends.len());
+    let mut start = 0;
+    for end in ends {
+        if end > start {
+            segments.push(&text[start..end]);
+            start = end;
+        }
+    }
+    segments
+}
+
+/// How far a reveal has got, plus the live pace and pause state that the
+/// playback controls adjust.
+#[derive(Debug, Clone)]
+pub(crate) struct Progress<'a> {
+    text: &'a str,
+    /// Byte offset where each unit ends.
+    ends: Vec<usize>,
+    /// Byte offset where each stanza ends.
+    stanza_ends: Vec<usize>,
+    /// Number of units on screen.
+    shown: usize,
+    speed_ms: u64,
+    /// The speed `pauses` are given for; they scale with changes to it.
+    base_speed_ms: u64,
+    pauses: Pauses,
+    markup: Markup,
+    paused: bool,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(text: &'a str, unit: Unit, speed_ms: u64) -> Self {
+        Self {
+            text,
+            ends: offsets(text, unit),
+            stanza_ends: offsets(text, Unit::Stanza),
+            shown: 0,
+            speed_ms,
+            base_speed_ms: speed_ms,
+            pauses: Pauses::default(),
+            markup: Markup::default(),
+            paused: false,
+        }
+    }
+
+    /// Holds units ending in punctuation or at a line or stanza break longer.
+    pub fn with_pauses(mut self, pauses: Pauses) -> Self {
+        self.pauses = pauses;
+        self
+    }
+
+    /// Everything revealed so far.
+    pub fn visible(&self) -> &'a str {
+        &self.text[..self.visible_len()]
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.shown == self.ends.len()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn speed_ms(&self) -> u64 {
+        self.speed_ms
+    }
+
+    /// Follows the speed changes and holds in the writing's markup. Speeds
+    /// from the markup scale with the live controls like the reader's own.
+    pub fn with_markup(mut self, markup: &Markup) -> Self {
+        self.markup = markup.clone();
+        self
+    }
+
+    /// Reveals the next unit, returning it with how long it should stay
+    /// before the one after: `speed_ms` per character plus any pause.
+    pub fn advance(&mut self) -> Option<(&'a str, Duration)> {
+        if self.is_complete() {
+            return None;
+        }
+
+        let from = self.visible_len();
+        self.shown += 1;
+        let to = self.visible_len();
+        let unit = &self.text[from..to];
+        let typing: u64 = unit
+            .grapheme_indices(true)
+            .map(|(i, _)| self.scaled(self.markup.speed_at(from + i).unwrap_or(self.base_speed_ms)))
+            .sum();
+        let pause = if self.is_complete() {
+            Duration::ZERO
+        } else {
+            let stanza_break = self.stanza_ends.binary_search(&to).is_ok();
+            let pause = self.pauses.after(&self.text[..to], unit, stanza_break);
+            Duration::from_millis(self.scaled(pause)) + self.markup.pause_within(from, to)
+        };
+        Some((unit, Duration::from_millis(typing) + pause))
+    }
+
+    /// How long the whole reveal takes without interruptions.
+    pub fn duration(&self) -> Duration {
+        let mut rest = Self {
+            shown: 0,
+            ..self.clone()
+        };
+        std::iter::from_fn(|| rest.advance().map(|(_, wait)| wait)).sum()
+    }
+
+    /// Reveals the rest of the current stanza, returning the newly shown text.
+    pub fn finish_stanza(&mut self) -> &'a str {
+        let from = self.visible_len();
+        let target = self
+            .stanza_ends
+            .iter()
+            .copied()
+            .find(|&end| end > from)
+            .unwrap_or(self.text.len());
+        while self.visible_len() < target && !self.is_complete() {
+            self.shown += 1;
+        }
+        &self.text[from..self.visible_len()]
+    }
+
+    /// Reveals everything, returning the newly shown text.
+    pub fn reveal_all(&mut self) -> &'a str {
+        let from = self.visible_len();
+        self.shown = self.ends.len();
+        &self.text[from..]
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Shortens the per-character delay by a fifth.
+    pub fn faster(&mut self) {
+        self.speed_ms = (self.speed_ms * 4 / 5).max(MIN_SPEED);
+    }
+
+    /// Lengthens the per-character delay by a quarter.
+    pub fn slower(&mut self) {
+        self.speed_ms = (self.speed_ms * 5 / 4)
+            .max(self.speed_ms + 1)
+            .min(MAX_SPEED);
+    }
+
+    /// `ms` at the starting speed, adjusted to the current one.
+    fn scaled(&self, ms: u64) -> u64 {
+        ms * self.speed_ms / self.base_speed_ms.max(1)
+    }
+
+    fn visible_len(&self) -> usize {
+        match self.shown.checked_sub(1) {
+            Some(last) => self.ends[last],
+            None => 0,
+        }
+    }
+}
+
+/// A reveal's length as `list` and the browser show it: `42s`, `3m 05s` or
+/// `1h 02m`.
+pub fn reading_time(duration: Duration) -> String {
+    let secs = duration.as_secs_f64().round() as u64;
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn offsets(text: &str, unit: Unit) -> Vec<usize> {
+    segments(text, unit)
+        .iter()
+        .scan(0, |offset, segment| {
+            *offset += segment.len();
+            Some(*offset)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STANZAS: &str = "Out of the night\nthat covers me,\n\n\nIn the fell clutch\n";
+
+    #[test]
+    fn segments_rejoin_to_the_original_text() {
+        for unit in [Unit::Char, Unit::Word, Unit::Line, Unit::Stanza] {
+            assert_eq!(segments(STANZAS, unit).concat(), STANZAS, "{unit}");
+        }
+        assert!(segments("", Unit::Word).is_empty());
+    }
+
+    #[test]
+    fn segments_split_on_unit_boundaries() {
+        assert_eq!(segments("héy", Unit::Char), ["h", "é", "y"]);
+        assert_eq!(segments("e\u{301}!", Unit::Char), ["e\u{301}", "!"]);
+        assert_eq!(
+            segments("  one two\nthree ", Unit::Word),
+            ["  ", "one ", "two\n", "three "]
+        );
+        assert_eq!(segments("a\nb\n\nc", Unit::Line), ["a\n", "b\n", "\n", "c"]);
+        assert_eq!(
+            segments(STANZAS, Unit::Stanza),
+            [
+                "Out of the night\nthat covers me,\n\n\n",
+                "In the fell clutch\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_paces_units_by_their_length() {
+        let mut progress = Progress::new("ab cd", Unit::Word, 10);
+        assert_eq!(progress.visible(), "");
+
+        assert_eq!(progress.advance(), Some(("ab ", Duration::from_millis(30))));
+        assert_eq!(progress.advance(), Some(("cd", Duration::from_millis(20))));
+        assert!(progress.is_complete());
+        assert_eq!(progress.advance(), None);
+        assert_eq!(progress.visible(), "ab cd");
+    }
+
+    #[test]
+    fn progress_skips_to_stanza_end_and_to_the_end() {
+        let mut progress = Progress::new(STANZAS, Unit::Char, 10);
+        progress.advance();
+        assert_eq!(
+            progress.finish_stanza(),
+            "ut of the night\nthat covers me,\n\n\n"
+        );
+        assert_eq!(progress.finish_stanza(), "In the fell clutch\n");
+        assert!(progress.is_complete());
+        assert_eq!(progress.finish_stanza(), "");
+
+        let mut progress = Progress::new(STANZAS, Unit::Line, 10);
+        assert_eq!(progress.reveal_all(), STANZAS);
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn pauses_follow_punctuation_lines_and_stanzas() {
+        let pauses = Pauses {
+            comma: 1,
+            period: 2,
+            dash: 3,
+            line: 10,
+            stanza: 100,
+        };
+        let waits = |text, unit| {
+            let mut progress = Progress::new(text, unit, 10).with_pauses(pauses);
+            std::iter::from_fn(|| progress.advance())
+                .map(|(unit, wait)| (unit, wait.as_millis()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            waits("me, “so.” ok", Unit::Word),
+            [("me, ", 41), ("“so.” ", 62), ("ok", 20)]
+        );
+        assert_eq!(
+            waits("a—b,\n\n\nc.\nd", Unit::Line),
+            [
+                ("a—b,\n", 60),
+                ("\n", 10),
+                ("\n", 110),
+                ("c.\n", 40),
+                ("d", 10)
+            ]
+        );
+        let chars = waits("a,\n\nb", Unit::Char);
+        let chars: Vec<_> = chars.iter().map(|&(_, wait)| wait).collect();
+        assert_eq!(chars, [10, 11, 20, 110, 10]);
+
+        // Pauses scale with live speed changes.
+        let mut progress = Progress::new("a.b", Unit::Char, 10).with_pauses(pauses);
+        progress.advance();
+        progress.slower();
+        assert_eq!(progress.advance().unwrap().1, Duration::from_millis(14));
+        let full = Progress::new("a.b", Unit::Char, 10).with_pauses(pauses);
+        assert_eq!(full.duration(), Duration::from_millis(32));
+    }
+
+    #[test]
+    fn markup_changes_speed_and_adds_holds() {
+        let (text, markup) = crate::markup::parse("ab{speed:50}c{pause:1s}{/speed}d").unwrap();
+        let mut progress = Progress::new(&text, Unit::Char, 10).with_markup(&markup);
+        let waits: Vec<_> = std::iter::from_fn(|| progress.advance())
+            .map(|(_, wait)| wait.as_millis())
+            .collect();
+        assert_eq!(waits, [10, 10, 1050, 10]);
+
+        let mut progress = Progress::new(&text, Unit::Word, 10).with_markup(&markup);
+        progress.faster();
+        assert_eq!(progress.advance().unwrap().1, Duration::from_millis(64));
+    }
+
+    #[test]
+    fn speed_changes_stay_within_bounds() {
+        let mut progress = Progress::new("x", Unit::Char, 25);
+        progress.faster();
+        assert_eq!(progress.speed_ms(), 20);
+        progress.slower();
+        assert_eq!(progress.speed_ms(), 25);
+
+        let mut fastest = Progress::new("x", Unit::Char, MIN_SPEED);
+        fastest.faster();
+        assert_eq!(fastest.speed_ms(), MIN_SPEED);
+        fastest.slower();
+        assert_eq!(fastest.speed_ms(), MIN_SPEED + 1);
+
+        let mut slowest = Progress::new("x", Unit::Char, MAX_SPEED);
+        slowest.slower();
+        assert_eq!(slowest.speed_ms(), MAX_SPEED);
+
+        progress.toggle_pause();
+        assert!(progress.is_paused());
+    }
+
+    #[test]
+    fn mixed_scripts_reveal_one_grapheme_at_a_time() {
+        let text = "한국어 詩\n👩‍👩‍👧 cafe\u{301}";
+        assert_eq!(
+            segments(text, Unit::Char),
+            [
+                "한",
+                "국",
+                "어",
+                " ",
+                "詩",
+                "\n",
+                "👩‍👩‍👧",
+                " ",
+                "c",
+                "a",
+                "f",
+                "e\u{301}"
+            ]
+        );
+        assert_eq!(
+            segments(text, Unit::Word),
+            ["한국어 ", "詩\n", "👩‍👩‍👧 ", "cafe\u{301}"]
+        );
+
+        let mut progress = Progress::new("👩‍👩‍👧 ok", Unit::Word, 10);
+        assert_eq!(progress.advance(), Some(("👩‍👩‍👧 ", Duration::from_millis(20))));
+    }
+
+    #[test]
+    fn reading_time_is_compact() {
+        assert_eq!(reading_time(Duration::from_millis(41_600)), "42s");
+        assert_eq!(reading_time(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(reading_time(Duration::from_secs(3720)), "1h 02m");
+    }
+
+    #[test]
+    fn units_parse_case_insensitively() {
+        assert_eq!("Stanza".parse::<Unit>().unwrap(), Unit::Stanza);
+        assert_eq!("character".parse::<Unit>().unwrap(), Unit::Char);
+        assert!("paragraph".parse::<Unit>().is_err());
+    }
+}
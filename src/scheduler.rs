@@ -0,0 +1,234 @@
+//! The reveal scheduler: what every renderer is driven by.
+//!
+//! A [`Scheduler`] owns a reveal's progress and layout. Each
+//! [`draw`](Scheduler::draw) works out what changed since the last one and
+//! hands a [`Renderer`] just that, grapheme by grapheme, so an output mode
+//! only has to know how to put a grapheme on its screen.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use anyhow::Result;
+use ratatui::style::Style;
+
+use crate::controls::{self, Control};
+use crate::engine::Playback;
+use crate::render::Renderer;
+use crate::reveal::Progress;
+use crate::viewport::{self, Scroll, Viewport};
+use crate::writing::Writing;
+
+/// What the renderer was last given.
+#[derive(Debug, Clone, Copy)]
+struct Shown {
+    /// The first row in view.
+    top: usize,
+    /// How much of the text was emitted.
+    end: usize,
+    /// The row the renderer's cursor is on.
+    row: usize,
+}
+
+/// A writing's reveal, drawn a step at a time through any [`Renderer`].
+pub struct Scheduler<'a> {
+    text: &'a str,
+    playback: Playback<'a>,
+    progress: Progress<'a>,
+    view: Viewport,
+    styled: Vec<(usize, &'a str, Style)>,
+    area: Option<(u16, u16)>,
+    rows: Vec<Range<usize>>,
+    shown: Option<Shown>,
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new(writing: &'a Writing, playback: &Playback<'a>) -> Self {
+        let text = writing.body.as_str();
+        Self {
+            text,
+            playback: *playback,
+            progress: Progress::new(text, playback.unit, playback.speed_ms)
+                .with_pauses(playback.pauses)
+                .with_markup(&writing.markup),
+            view: Viewport::default(),
+            styled: playback
+                .theme
+                .styled_graphemes(text, &writing.markup)
+                .collect(),
+            area: None,
+            rows: Vec::new(),
+            shown: None,
+        }
+    }
+
+    pub fn progress(&self) -> &Progress<'a> {
+        &self.progress
+    }
+
+    /// Reveals the next unit, returning how long it should stay up, or
+    /// `None` once everything is shown.
+    pub fn advance(&mut self) -> Option<Duration> {
+        self.progress.advance().map(|(_, pause)| pause)
+    }
+
+    /// Answers one of the playback controls.
+    pub fn control(&mut self, control: Control) {
+        controls::apply(&mut self.progress, control);
+    }
+
+    pub fn scroll(&mut self, scroll: Scroll) {
+        self.view.scroll(scroll);
+    }
+
+    /// Brings `renderer` up to date: only the graphemes revealed since the
+    /// last draw, unless the view moved or the area changed, in which case
+    /// the renderer is cleared and everything in view emitted again. Each
+    /// draw ends with [`Renderer::present`].
+    pub fn draw(&mut self, renderer: &mut impl Renderer) -> Result<()> {
+        let area = renderer.area()?;
+        self.lay_out(area);
+
+        let visible = self.progress.visible().len();
+        match area {
+            Some((_, height)) => self.emit_rows(renderer, usize::from(height).max(1))?,
+            None => {
+                let from = self.shown.map_or(0, |shown| shown.end);
+                for &(_, grapheme, style) in self.range(from, visible) {
+                    if grapheme.ends_with('\n') {
+                        renderer.newline()?;
+                    } else {
+                        renderer.emit(grapheme, style)?;
+                    }
+                }
+                self.shown = Some(Shown {
+                    top: 0,
+                    end: visible,
+                    row: 0,
+                });
+            }
+        }
+
+        let status = controls::status_line(
+            &self.progress,
+            self.playback.navigable,
+            self.playback.exit_keys,
+        );
+        renderer.present(&status)
+    }
+
+    /// The first row in view on a screen of `area`, as [`draw`](Self::draw)
+    /// would show it there.
+    pub fn top(&mut self, area: (u16, u16)) -> usize {
+        self.lay_out(Some(area));
+        let height = usize::from(area.1).max(1);
+        self.view.top_for(&self.rows, &self.progress, height)
+    }
+
+    /// Wraps the text anew when the area changed, which also means the
+    /// renderer has to start over.
+    fn lay_out(&mut self, area: Option<(u16, u16)>) {
+        if area != self.area {
+            self.area = area;
+            self.rows = match area {
+                Some((width, _)) => viewport::wrap(self.text, width.into()),
+                None => Vec::new(),
+            };
+            self.shown = None;
+        }
+    }
+
+    /// The rows in view from `top`, with a renderer that lays them out.
+    fn emit_rows(&mut self, renderer: &mut impl Renderer, height: usize) -> Result<()> {
+        let visible = self.progress.visible().len();
+        let top = self.view.top_for(&self.rows, &self.progress, height);
+        let mut shown = match self.shown {
+            Some(shown) if shown.top == top && shown.end <= visible => shown,
+            _ => {
+                renderer.clear()?;
+                Shown {
+                    top,
+                    end: 0,
+                    row: top,
+                }
+            }
+        };
+
+        for &(i, grapheme, style) in self.range(shown.end, visible) {
+            let Some((row, _)) = viewport::locate(self.text, &self.rows, i) else {
+                continue;
+            };
+            if !(top..top + height).contains(&row) {
+                continue;
+            }
+            while shown.row < row {
+                renderer.newline()?;
+                shown.row += 1;
+            }
+            renderer.emit(grapheme, style)?;
+        }
+        shown.end = visible;
+        self.shown = Some(shown);
+        Ok(())
+    }
+
+    /// The styled graphemes starting in `from..to`.
+    fn range(&self, from: usize, to: usize) -> &[(usize, &'a str, Style)] {
+        let first = self.styled.partition_point(|&(i, ..)| i < from);
+        let last = self.styled.partition_point(|&(i, ..)| i < to);
+        &self.styled[first..last]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+    use crate::engine::RevealEngine;
+    use crate::render::Memory;
+    use crate::reveal::Unit;
+    use crate::theme::Theme;
+
+    fn engine() -> RevealEngine {
+        let mut settings = Settings::default();
+        settings.unit.value = Unit::Word;
+        RevealEngine::new(settings, Theme::default())
+    }
+
+    #[test]
+    fn only_new_graphemes_are_emitted_until_the_view_moves() {
+        let engine = engine();
+        let playback = Playback::new(&engine);
+        let writing = Writing::parse("x", "one two\nthree\n").unwrap();
+        let mut scheduler = Scheduler::new(&writing, &playback);
+        let mut memory = Memory::with_size((20, 1));
+
+        scheduler.draw(&mut memory).unwrap();
+        while scheduler.advance().is_some() {
+            scheduler.draw(&mut memory).unwrap();
+        }
+        let screens: Vec<_> = memory.frames.iter().map(|f| f.lines.join("|")).collect();
+        assert_eq!(screens, ["", "one ", "one two", "three"]);
+        assert_eq!(memory.clears, 2);
+        assert!(memory.frames.last().unwrap().status.starts_with("■ done"));
+
+        scheduler.scroll(Scroll::Top);
+        scheduler.draw(&mut memory).unwrap();
+        assert_eq!(memory.lines, ["one two"]);
+        assert_eq!(memory.clears, 3);
+    }
+
+    #[test]
+    fn streams_without_an_area_are_neither_wrapped_nor_cleared() {
+        let engine = engine();
+        let playback = Playback::new(&engine);
+        let writing = Writing::parse("x", "a long line\nb\n").unwrap();
+        let mut scheduler = Scheduler::new(&writing, &playback);
+        let mut memory = Memory::new();
+
+        scheduler.control(Control::RevealAll);
+        scheduler.draw(&mut memory).unwrap();
+        assert_eq!(memory.lines, ["a long line", "b", ""]);
+        assert_eq!(memory.clears, 0);
+        assert!(scheduler.progress().is_complete());
+    }
+}
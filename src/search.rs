@@ -0,0 +1,260 @@
+//! The `search` command: finding a writing by a remembered phrase.
+//!
+//! Every writing in the library is scanned line by line. Results are printed
+//! grep-style, `name:line:text`, with `name-line-text` for context lines.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use anyhow::{Context, Result};
+use crossterm::style::Stylize;
+use regex::{Regex, RegexBuilder};
+
+use unveilox::library::{Library, Skipped};
+use unveilox::reveal::{self, Unit};
+use unveilox::Writing;
+
+/// How the query is interpreted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub ignore_case: bool,
+    /// Only match whole words.
+    pub whole_word: bool,
+    /// Treat the query as a regular expression instead of literal text.
+    pub regex: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn new(query: &str, options: Options) -> Result<Self> {
+        let mut source = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        if options.whole_word {
+            source = format!(r"\b(?:{source})\b");
+        }
+
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(options.ignore_case)
+            .build()
+            .with_context(|| format!("invalid search pattern `{query}`"))?;
+        Ok(Self { regex })
+    }
+
+    fn find_in(&self, line: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
+/// A matching line, numbered from 1 within the writing's text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch {
+    pub number: usize,
+    /// Byte offset of the first match in the writing's body.
+    pub offset: usize,
+    /// Byte ranges of the matches within the line.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A writing with at least one matching line.
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub writing: Writing,
+    pub lines: Vec<LineMatch>,
+}
+
+/// Every line of `writing` that `pattern` matches.
+pub fn find(writing: &Writing, pattern: &Pattern) -> Vec<LineMatch> {
+    let mut matches = Vec::new();
+    let mut offset = 0;
+    for (index, line) in writing.body.split_inclusive('\n').enumerate() {
+        let ranges = pattern.find_in(line.trim_end_matches(['\r', '\n']));
+        if let Some(first) = ranges.first() {
+            matches.push(LineMatch {
+                number: index + 1,
+                offset: offset + first.start,
+                ranges,
+            });
+        }
+        offset += line.len();
+    }
+    matches
+}
+
+/// Searches every writing in `library`, in name order. Writings that fail to
+/// load are returned apart.
+pub fn search(library: &Library, pattern: &Pattern) -> Result<(Vec<Found>, Vec<Skipped>)> {
+    let (writings, skipped) = library.read_all()?;
+    let found = writings
+        .into_iter()
+        .filter_map(|(_, writing)| {
+            let lines = find(&writing, pattern);
+            (!lines.is_empty()).then_some(Found { writing, lines })
+        })
+        .collect();
+    Ok((found, skipped))
+}
+
+/// Matching lines with `context` lines around them. Separate groups are
+/// divided by `--`, and matches are highlighted when `highlight` is set.
+pub fn render(found: &[Found], context: usize, highlight: bool) -> String {
+    let mut out = String::new();
+    for result in found {
+        let lines: Vec<&str> = result
+            .writing
+            .body
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect();
+        let shown: BTreeSet<usize> = result
+            .lines
+            .iter()
+            .flat_map(|m| {
+                let index = m.number - 1;
+                index.saturating_sub(context)..=(index + context).min(lines.len() - 1)
+            })
+            .collect();
+
+        if !out.is_empty() {
+            out.push_str("--\n");
+        }
+        let mut previous: Option<usize> = None;
+        for index in shown {
+            if previous.is_some_and(|previous| index > previous + 1) {
+                out.push_str("--\n");
+            }
+            previous = Some(index);
+
+            let line = lines[index];
+            let name = &result.writing.name;
+            let number = index + 1;
+            match result.lines.iter().find(|m| m.number == number) {
+                Some(m) => {
+                    let text = if highlight {
+                        highlighted(line, &m.ranges)
+                    } else {
+                        line.to_string()
+                    };
+                    out.push_str(&format!("{name}:{number}:{text}\n"));
+                }
+                None => out.push_str(&format!("{name}-{number}-{line}\n")),
+            }
+        }
+    }
+    out
+}
+
+fn highlighted(line: &str, ranges: &[Range<usize>]) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for range in ranges {
+        out.push_str(&line[last..range.start]);
+        out.push_str(&line[range.clone()].red().bold().to_string());
+        last = range.end;
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+/// Byte offset where the stanza holding byte `offset` starts.
+pub fn stanza_start(body: &str, offset: usize) -> usize {
+    let mut start = 0;
+    for stanza in reveal::segments(body, Unit::Stanza) {
+        if offset < start + stanza.len() {
+            break;
+        }
+        start += stanza.len();
+    }
+    start.min(body.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POEM: &str = "Out of the night that covers me,\nBlack as the pit\n\nIn the fell clutch of circumstance\nI have not winced\n";
+
+    fn writing() -> Writing {
+        Writing::parse("invictus", POEM).unwrap()
+    }
+
+    fn numbers(query: &str, options: Options) -> Vec<usize> {
+        let pattern = Pattern::new(query, options).unwrap();
+        find(&writing(), &pattern)
+            .iter()
+            .map(|m| m.number)
+            .collect()
+    }
+
+    #[test]
+    fn options_change_what_matches() {
+        assert_eq!(numbers("the", Options::default()), [1, 2, 4]);
+        assert_eq!(numbers("in", Options::default()), [5]);
+        let ignore_case = Options {
+            ignore_case: true,
+            ..Options::default()
+        };
+        assert_eq!(numbers("in", ignore_case), [4, 5]);
+        let whole_word = Options {
+            whole_word: true,
+            ignore_case: true,
+            ..Options::default()
+        };
+        assert_eq!(numbers("in", whole_word), [4]);
+        let regex = Options {
+            regex: true,
+            ..Options::default()
+        };
+        assert_eq!(numbers(r"\bc\w+(s|ch)\b", regex), [1, 4]);
+        assert_eq!(numbers("c.vers", Options::default()), Vec::<usize>::new());
+        assert!(Pattern::new("(", regex).is_err());
+    }
+
+    #[test]
+    fn render_shows_context_and_separates_groups() {
+        let pattern = Pattern::new("clutch", Options::default()).unwrap();
+        let found = [Found {
+            writing: writing(),
+            lines: find(&writing(), &pattern),
+        }];
+        assert_eq!(
+            render(&found, 1, false),
+            "invictus-3-\ninvictus:4:In the fell clutch of circumstance\ninvictus-5-I have not winced\n"
+        );
+
+        let pattern = Pattern::new(
+            "Out|winced",
+            Options {
+                regex: true,
+                ..Options::default()
+            },
+        )
+        .unwrap();
+        let found = [Found {
+            writing: writing(),
+            lines: find(&writing(), &pattern),
+        }];
+        let out = render(&found, 0, false);
+        assert_eq!(
+            out,
+            "invictus:1:Out of the night that covers me,\n--\ninvictus:5:I have not winced\n"
+        );
+    }
+
+    #[test]
+    fn reveal_can_start_at_the_matching_stanza() {
+        let offset = POEM.find("winced").unwrap();
+        assert!(POEM[stanza_start(POEM, offset)..].starts_with("In the fell clutch"));
+        assert_eq!(stanza_start(POEM, 3), 0);
+    }
+}
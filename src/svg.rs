@@ -0,0 +1,239 @@
+//! Animated SVG export. Each shot is a group shown and hidden by CSS
+//! keyframes, each grapheme appears at the moment the reveal reaches it, and
+//! scrolling is a stepped translation of the text.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use ratatui::style::Modifier;
+
+use crate::export::{self, Cell, Film, Shot};
+
+/// Font stack used when none is given.
+pub const DEFAULT_FONT: &str = "'DejaVu Sans Mono', Menlo, Consolas, monospace";
+/// Width of a cell and height of a line, in ems of a typical monospace font.
+const CELL_WIDTH: f32 = 0.6;
+const LINE_HEIGHT: f32 = 1.25;
+
+/// The SVG document for `film`, set in `font` at `font_size` pixels.
+pub fn render(film: &Film, font: &str, font_size: u16) -> String {
+    let size = f32::from(font_size);
+    let advance = size * CELL_WIDTH;
+    let line = size * LINE_HEIGHT;
+    let width = advance * f32::from(film.size.width);
+    let height = line * f32::from(film.size.height);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#,
+        width = pixels(width),
+        height = pixels(height),
+    );
+    let _ = writeln!(
+        svg,
+        "<style>\n\
+         text {{ font-family: {}; font-size: {size}px; white-space: pre; fill: {}; }}\n\
+         .shot, .g {{ opacity: 0; animation-duration: 0s; animation-fill-mode: forwards; }}\n\
+         .g {{ animation-name: on; }}\n\
+         @keyframes on {{ to {{ opacity: 1; }} }}\n\
+         @keyframes off {{ to {{ opacity: 0; }} }}\n\
+         </style>",
+        escape(font),
+        export::hex(film.foreground),
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        export::hex(film.background)
+    );
+
+    for (index, shot) in film.shots.iter().enumerate() {
+        let last = index + 1 == film.shots.len();
+        let style = if last {
+            format!(
+                "animation-name: on; animation-delay: {}",
+                seconds(shot.start)
+            )
+        } else {
+            format!(
+                "animation-name: on, off; animation-delay: {}, {}",
+                seconds(shot.start),
+                seconds(shot.end)
+            )
+        };
+        let _ = writeln!(svg, r#"<g class="shot" style="{style}">"#);
+        let scrolls = scrolling(shot, index, line);
+        if let Some((keyframes, animation)) = &scrolls {
+            let _ = writeln!(svg, "<style>{keyframes}</style>");
+            let _ = writeln!(svg, r#"<g style="animation: {animation}">"#);
+        }
+        for row in rows(&shot.cells) {
+            let baseline = row[0].row as f32 * line + size;
+            let _ = write!(svg, r#"<text y="{}">"#, pixels(baseline));
+            for cell in row {
+                tspan(&mut svg, shot, cell, cell.col as f32 * advance, film);
+            }
+            let _ = writeln!(svg, "</text>");
+        }
+        if scrolls.is_some() {
+            let _ = writeln!(svg, "</g>");
+        }
+        let _ = writeln!(svg, "</g>");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn tspan(svg: &mut String, shot: &Shot, cell: &Cell, x: f32, film: &Film) {
+    let mut attributes = format!(r#" x="{}""#, pixels(x));
+    let ink = export::ink(cell.style, film.foreground, film.background);
+    if ink != film.foreground {
+        let _ = write!(attributes, r#" fill="{}""#, export::hex(ink));
+    }
+    let modifiers = cell.style.add_modifier;
+    if modifiers.contains(Modifier::BOLD) {
+        attributes.push_str(r#" font-weight="bold""#);
+    }
+    if modifiers.contains(Modifier::ITALIC) {
+        attributes.push_str(r#" font-style="italic""#);
+    }
+    if modifiers.contains(Modifier::UNDERLINED) {
+        attributes.push_str(r#" text-decoration="underline""#);
+    }
+    // What shows with the shot needs no animation of its own.
+    let shown = shot.shown_at(cell.offset);
+    if shown > shot.start {
+        let _ = write!(
+            attributes,
+            r#" class="g" style="animation-delay: {}""#,
+            seconds(shown)
+        );
+    }
+    let _ = write!(svg, "<tspan{attributes}>{}</tspan>", escape(&cell.grapheme));
+}
+
+/// Keyframes and the animation moving the shot's text up as it scrolls, if
+/// it ever does.
+fn scrolling(shot: &Shot, index: usize, line: f32) -> Option<(String, String)> {
+    let mut changes = Vec::new();
+    let mut top = 0;
+    for step in &shot.steps {
+        if step.top != top {
+            top = step.top;
+            changes.push((step.at - shot.start, top));
+        }
+    }
+    let length = changes.last()?.0;
+    if length.is_zero() {
+        return None;
+    }
+
+    let name = format!("scroll{index}");
+    let mut keyframes = format!("@keyframes {name} {{ 0% {{ transform: translateY(0px); }}");
+    for (at, top) in changes {
+        let percent = at.as_secs_f64() / length.as_secs_f64() * 100.0;
+        let _ = write!(
+            keyframes,
+            " {percent:.4}% {{ transform: translateY({}px); }}",
+            pixels(-(top as f32) * line)
+        );
+    }
+    keyframes.push_str(" }");
+    let animation = format!(
+        "{name} {} step-end {} forwards",
+        seconds(length),
+        seconds(shot.start)
+    );
+    Some((keyframes, animation))
+}
+
+/// The cells to draw, grouped by row; blanks are left out.
+fn rows(cells: &[Cell]) -> Vec<Vec<&Cell>> {
+    let mut rows: Vec<Vec<&Cell>> = Vec::new();
+    for cell in cells.iter().filter(|cell| !cell.grapheme.trim().is_empty()) {
+        match rows.last_mut() {
+            Some(row) if row[0].row == cell.row => row.push(cell),
+            _ => rows.push(vec![cell]),
+        }
+    }
+    rows
+}
+
+/// `value` to two decimals at most, as `12.5` rather than `12.50001`.
+fn pixels(value: f32) -> String {
+    let fixed = format!("{value:.2}");
+    let trimmed = fixed.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{plan, Timing};
+    use crate::record::Size;
+    use crate::reveal::{Pauses, Unit};
+    use crate::theme::Theme;
+    use crate::writing::Writing;
+
+    fn film(source: &str, size: Size) -> Film {
+        let timing = Timing {
+            unit: Unit::Char,
+            speed_ms: 100,
+            pauses: Pauses::default(),
+            card: Duration::from_secs(3),
+            tail: Duration::from_secs(2),
+        };
+        let writing = Writing::parse("x", source).unwrap();
+        plan(&writing, &Theme::default(), &timing, size, None)
+    }
+
+    #[test]
+    fn graphemes_appear_when_the_reveal_reaches_them() {
+        let svg = render(
+            &film("---\ntitle: A<B\n---\nI *am*\n", Size::default()),
+            "mono",
+            20,
+        );
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="960" height="600""#)
+        );
+        assert!(svg.contains("font-family: mono; font-size: 20px"));
+        assert!(svg.contains(r#"animation-delay: 0.000s, 3.000s"#));
+        assert!(svg.contains(
+            r#"<tspan x="456" font-weight="bold">A</tspan><tspan x="468" font-weight="bold">&lt;</tspan>"#
+        ));
+        assert!(svg.contains(r##"<tspan x="0" fill="#cd00cd">I</tspan>"##));
+        assert!(svg.contains(
+            r#"<tspan x="24" font-style="italic" class="g" style="animation-delay: 3.200s">a</tspan>"#
+        ));
+        assert!(!svg.contains("scroll"));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+    }
+
+    #[test]
+    fn long_texts_scroll_in_steps() {
+        let size = Size {
+            width: 10,
+            height: 2,
+        };
+        let svg = render(&film("1\n2\n3\n", size), "mono", 10);
+        assert!(svg.contains("@keyframes scroll0 { 0% { transform: translateY(0px); }"));
+        assert!(svg.contains("100.0000% { transform: translateY(-"));
+        assert!(svg.contains("step-end 0.000s forwards"));
+    }
+}
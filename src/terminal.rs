@@ -0,0 +1,91 @@
+//! Taking over the terminal for a reveal, and giving it back.
+
+use std::io;
+
+use anyhow::Result;
+use crossterm::{cursor, event, execute, style, terminal};
+
+/// The alternate screen in raw mode, with the mouse captured and the cursor
+/// hidden if asked. Dropping the guard restores the terminal even on errors.
+pub struct TerminalGuard {
+    raw_mode: bool,
+    alt_screen: bool,
+    /// Mouse events are captured so the wheel can scroll.
+    mouse_captured: bool,
+    cursor_hidden: bool,
+}
+
+impl TerminalGuard {
+    pub fn enter(hide_cursor: bool) -> Result<Self> {
+        let mut stdout = io::stdout();
+        execute!(
+            stdout,
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture
+        )?;
+        terminal::enable_raw_mode()?;
+
+        if hide_cursor {
+            execute!(stdout, cursor::Hide)?;
+        }
+
+        Ok(Self {
+            raw_mode: true,
+            alt_screen: true,
+            mouse_captured: true,
+            cursor_hidden: hide_cursor,
+        })
+    }
+
+    fn show_cursor(&mut self) -> Result<()> {
+        if self.cursor_hidden {
+            let mut stdout = io::stdout();
+            execute!(stdout, cursor::Show)?;
+            self.cursor_hidden = false;
+        }
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> Result<()> {
+        if self.raw_mode {
+            terminal::disable_raw_mode()?;
+            self.raw_mode = false;
+        }
+        Ok(())
+    }
+
+    fn release_mouse(&mut self) -> Result<()> {
+        if self.mouse_captured {
+            let mut stdout = io::stdout();
+            execute!(stdout, event::DisableMouseCapture)?;
+            self.mouse_captured = false;
+        }
+        Ok(())
+    }
+
+    fn leave_alt_screen(&mut self) -> Result<()> {
+        if self.alt_screen {
+            let mut stdout = io::stdout();
+            execute!(stdout, style::ResetColor, terminal::LeaveAlternateScreen)?;
+            self.alt_screen = false;
+        }
+        Ok(())
+    }
+
+    /// Restores the terminal, reporting what dropping the guard would ignore.
+    pub fn finish(&mut self) -> Result<()> {
+        self.show_cursor()?;
+        self.release_mouse()?;
+        self.disable_raw_mode()?;
+        self.leave_alt_screen()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = self.show_cursor();
+        let _ = self.release_mouse();
+        let _ = self.disable_raw_mode();
+        let _ = self.leave_alt_screen();
+    }
+}
@@ -0,0 +1,551 @@
+//! Colour themes shared by the typewriter and TUI renderers.
+//!
+//! A theme is a foreground/background pair plus a list of accent colours and
+//! a [`Pattern`] deciding which character gets which accent. Besides the
+//! built-ins, themes can be loaded from TOML files:
+//!
+//! ```toml
+//! pattern = "word"            # plain | scatter | line | word
+//! foreground = "white"
+//! background = "#1d2021"
+//! accents = ["#fb4934", "#fabd2f", "#b8bb26"]
+//! bold = false
+//! ```
+//!
+//! `--theme <name>` looks for `~/.config/unveilox/themes/<name>.toml` before
+//! falling back to the built-ins; a value ending in `.toml` is read as a path.
+
+use std::env;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use crossterm::style::{Attribute, ContentStyle};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+use serde::Deserialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config;
+use crate::markup::Markup;
+
+const THEME_EXTENSION: &str = "toml";
+
+/// The names of the themes that need no file.
+pub const BUILT_IN: [&str; 6] = [
+    "default",
+    "mono",
+    "high-contrast",
+    "solarized",
+    "gradient",
+    "per-word",
+];
+
+/// Which characters get which accent colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pattern {
+    /// Every character in the foreground colour.
+    Plain,
+    /// The first two accents sprinkled diagonally through the text.
+    Scatter,
+    /// One accent per line, sweeping back and forth through the list.
+    Line,
+    /// One accent per word, cycling through the list.
+    Word,
+}
+
+/// Where a grapheme sits in the text, for patterns that depend on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Position {
+    pub row: usize,
+    pub col: usize,
+    pub word: usize,
+}
+
+/// Walks a text handing out the [`Position`] of each grapheme cluster.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    next: Position,
+    in_word: bool,
+    seen_word: bool,
+}
+
+impl Tracker {
+    pub(crate) fn place(&mut self, grapheme: &str) -> Position {
+        if grapheme.chars().all(char::is_whitespace) {
+            self.in_word = false;
+        } else if !self.in_word {
+            if self.seen_word {
+                self.next.word += 1;
+            }
+            self.in_word = true;
+            self.seen_word = true;
+        }
+
+        let at = self.next;
+        if grapheme.ends_with('\n') {
+            self.next.row += 1;
+            self.next.col = 0;
+        } else {
+            self.next.col += 1;
+        }
+        at
+    }
+}
+
+/// Colours and emphasis for a reveal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub pattern: Pattern,
+    pub foreground: Color,
+    pub background: Option<Color>,
+    pub accents: Vec<Color>,
+    pub bold: bool,
+    /// Whether colours from a writing's markup are shown.
+    pub colored: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::built_in("default").expect("default theme is built in")
+    }
+}
+
+impl Theme {
+    /// Resolves `name` to a theme file path, a user theme or a built-in.
+    pub fn load(name: &str) -> Result<Self> {
+        let trimmed = name.trim();
+        if trimmed.is_empty() {
+            bail!("Theme name must not be empty");
+        }
+
+        let as_path = Path::new(trimmed);
+        if as_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(THEME_EXTENSION))
+        {
+            return Self::from_file(as_path);
+        }
+
+        if let Some(path) = user_dir()
+            .map(|dir| dir.join(format!("{trimmed}.{THEME_EXTENSION}")))
+            .filter(|path| path.is_file())
+        {
+            return Self::from_file(&path);
+        }
+
+        Self::built_in(trimmed).with_context(|| {
+            format!(
+                "Unknown theme `{trimmed}` (built-in: {})",
+                BUILT_IN.join(", ")
+            )
+        })
+    }
+
+    /// Reads a theme from a TOML file, named after its stem.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = crate::library::read_path(path)?;
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("custom");
+        Self::parse(name, &text).with_context(|| format!("in theme {}", path.display()))
+    }
+
+    fn parse(name: &str, text: &str) -> Result<Self> {
+        let file: ThemeFile = toml::from_str(text)?;
+        let theme = Self {
+            name: name.to_string(),
+            pattern: file.pattern.unwrap_or(Pattern::Plain),
+            foreground: file
+                .foreground
+                .as_deref()
+                .map(parse_color)
+                .transpose()?
+                .unwrap_or(Color::Reset),
+            background: file.background.as_deref().map(parse_color).transpose()?,
+            accents: file
+                .accents
+                .iter()
+                .map(|c| parse_color(c))
+                .collect::<Result<_>>()?,
+            bold: file.bold,
+            colored: true,
+        };
+
+        if theme.pattern != Pattern::Plain && theme.accents.is_empty() {
+            bail!(
+                "pattern {:?} needs at least one accent colour",
+                theme.pattern
+            );
+        }
+
+        Ok(theme)
+    }
+
+    fn built_in(name: &str) -> Option<Self> {
+        let theme = |pattern, foreground, background, accents: &[Color], bold| Self {
+            name: name.to_ascii_lowercase(),
+            pattern,
+            foreground,
+            background,
+            accents: accents.to_vec(),
+            bold,
+            colored: true,
+        };
+
+        let theme = match name.to_ascii_lowercase().as_str() {
+            "default" => theme(
+                Pattern::Scatter,
+                Color::Reset,
+                None,
+                &[Color::Magenta, Color::Blue],
+                false,
+            ),
+            "mono" => theme(Pattern::Plain, Color::Reset, None, &[], false),
+            "high-contrast" => theme(Pattern::Plain, Color::White, Some(Color::Black), &[], true),
+            "solarized" => theme(
+                Pattern::Scatter,
+                Color::Rgb(0x83, 0x94, 0x96),
+                Some(Color::Rgb(0x00, 0x2b, 0x36)),
+                &[Color::Rgb(0xb5, 0x89, 0x00), Color::Rgb(0x26, 0x8b, 0xd2)],
+                false,
+            ),
+            "gradient" => theme(
+                Pattern::Line,
+                Color::Reset,
+                None,
+                &[
+                    Color::Rgb(0x5f, 0x87, 0xff),
+                    Color::Rgb(0x7f, 0x7f, 0xf7),
+                    Color::Rgb(0x9f, 0x77, 0xef),
+                    Color::Rgb(0xbf, 0x6f, 0xe7),
+                    Color::Rgb(0xdf, 0x67, 0xdf),
+                    Color::Rgb(0xff, 0x5f, 0xd7),
+                ],
+                false,
+            ),
+            "per-word" => theme(
+                Pattern::Word,
+                Color::Reset,
+                None,
+                &[
+                    Color::LightRed,
+                    Color::LightYellow,
+                    Color::LightGreen,
+                    Color::LightCyan,
+                    Color::LightBlue,
+                    Color::LightMagenta,
+                ],
+                false,
+            ),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// The same theme with every colour dropped, keeping only the weight.
+    pub fn without_color(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            pattern: Pattern::Plain,
+            foreground: Color::Reset,
+            background: None,
+            accents: Vec::new(),
+            bold: self.bold,
+            colored: false,
+        }
+    }
+
+    /// Style of the whole canvas: foreground, background and weight.
+    pub(crate) fn base_style(&self) -> Style {
+        let mut style = Style::default().fg(self.foreground);
+        if let Some(background) = self.background {
+            style = style.bg(background);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        style
+    }
+
+    /// Style of the character at `at`.
+    pub(crate) fn style(&self, at: Position) -> Style {
+        let accent = match self.pattern {
+            Pattern::Plain => None,
+            Pattern::Scatter => {
+                if (at.col + at.row) % 7 == 0 {
+                    self.accents.first()
+                } else if at.col % 5 == 0 {
+                    self.accents.get(1)
+                } else {
+                    None
+                }
+            }
+            Pattern::Line => self.accents.get(sweep(at.row, self.accents.len())),
+            Pattern::Word => self.accents.get(at.word % self.accents.len().max(1)),
+        };
+
+        match accent {
+            Some(color) => self.base_style().fg(*color),
+            None => self.base_style(),
+        }
+    }
+
+    /// Every grapheme cluster of `text` with its byte offset and style,
+    /// the `markup`'s looks included.
+    pub(crate) fn styled_graphemes<'t>(
+        &'t self,
+        text: &'t str,
+        markup: &'t Markup,
+    ) -> impl Iterator<Item = (usize, &'t str, Style)> + 't {
+        let mut tracker = Tracker::default();
+        text.grapheme_indices(true).map(move |(i, g)| {
+            let style = self.style(tracker.place(g));
+            (i, g, markup.style_at(i, style, self.colored))
+        })
+    }
+
+    /// The given `rows` of `text` (byte ranges into it) as lines of spans,
+    /// one span per run of equal style.
+    pub(crate) fn styled_rows(
+        &self,
+        text: &str,
+        markup: &Markup,
+        rows: &[Range<usize>],
+    ) -> Text<'static> {
+        let graphemes: Vec<_> = self.styled_graphemes(text, markup).collect();
+        let lines: Vec<Line> = rows
+            .iter()
+            .map(|row| {
+                let first = graphemes.partition_point(|&(i, _, _)| i < row.start);
+                let mut spans = Vec::new();
+                let mut run = String::new();
+                let mut run_style = self.base_style();
+
+                for &(_, grapheme, style) in graphemes[first..]
+                    .iter()
+                    .take_while(|&&(i, _, _)| i < row.end)
+                {
+                    if style != run_style && !run.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut run), run_style));
+                    }
+                    run_style = style;
+                    run.push_str(grapheme);
+                }
+                if !run.is_empty() {
+                    spans.push(Span::styled(run, run_style));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        Text::from(lines)
+    }
+}
+
+/// The same style for crossterm's direct output in typewriter mode.
+pub(crate) fn content_style(style: Style) -> ContentStyle {
+    let mut content = ContentStyle::new();
+    // `Reset` is the terminal's own colour, so there is nothing to emit.
+    let color = |c: Option<Color>| c.filter(|c| *c != Color::Reset).map(Into::into);
+    content.foreground_color = color(style.fg);
+    content.background_color = color(style.bg);
+    if style.add_modifier.contains(Modifier::BOLD) {
+        content.attributes.set(Attribute::Bold);
+    }
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        content.attributes.set(Attribute::Italic);
+    }
+    content
+}
+
+/// Index into `len` accents that walks 0, 1, .., len-1, len-2, .., 1, 0, ...
+fn sweep(row: usize, len: usize) -> usize {
+    if len <= 1 {
+        return 0;
+    }
+    let period = 2 * (len - 1);
+    let i = row % period;
+    if i < len {
+        i
+    } else {
+        period - i
+    }
+}
+
+/// The RGB value a colour shows as, using xterm's palette for named and
+/// indexed colours. `None` for `Reset`, which is up to the terminal.
+pub(crate) fn rgb(color: Color) -> Option<[u8; 3]> {
+    const ANSI: [[u8; 3]; 16] = [
+        [0x00, 0x00, 0x00],
+        [0xcd, 0x00, 0x00],
+        [0x00, 0xcd, 0x00],
+        [0xcd, 0xcd, 0x00],
+        [0x00, 0x00, 0xee],
+        [0xcd, 0x00, 0xcd],
+        [0x00, 0xcd, 0xcd],
+        [0xe5, 0xe5, 0xe5],
+        [0x7f, 0x7f, 0x7f],
+        [0xff, 0x00, 0x00],
+        [0x00, 0xff, 0x00],
+        [0xff, 0xff, 0x00],
+        [0x5c, 0x5c, 0xff],
+        [0xff, 0x00, 0xff],
+        [0x00, 0xff, 0xff],
+        [0xff, 0xff, 0xff],
+    ];
+    let indexed = |index: u8| match index {
+        0..=15 => ANSI[usize::from(index)],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let cube = index - 16;
+            [level(cube / 36), level(cube / 6 % 6), level(cube % 6)]
+        }
+        _ => [8 + (index - 232) * 10; 3],
+    };
+
+    Some(match color {
+        Color::Reset => return None,
+        Color::Black => ANSI[0],
+        Color::Red => ANSI[1],
+        Color::Green => ANSI[2],
+        Color::Yellow => ANSI[3],
+        Color::Blue => ANSI[4],
+        Color::Magenta => ANSI[5],
+        Color::Cyan => ANSI[6],
+        Color::Gray => ANSI[7],
+        Color::DarkGray => ANSI[8],
+        Color::LightRed => ANSI[9],
+        Color::LightGreen => ANSI[10],
+        Color::LightYellow => ANSI[11],
+        Color::LightBlue => ANSI[12],
+        Color::LightMagenta => ANSI[13],
+        Color::LightCyan => ANSI[14],
+        Color::White => ANSI[15],
+        Color::Rgb(r, g, b) => [r, g, b],
+        Color::Indexed(index) => indexed(index),
+    })
+}
+
+/// A colour name such as `red`, or `#rrggbb`.
+pub fn parse_color(raw: &str) -> Result<Color> {
+    Color::from_str(raw.trim()).map_err(|_| anyhow::anyhow!("`{raw}` is not a colour"))
+}
+
+/// Whether the user opted out of colour via `NO_COLOR` (<https://no-color.org>).
+pub fn no_color_requested() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// `~/.config/unveilox/themes`.
+pub(crate) fn user_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("themes"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    pattern: Option<Pattern>,
+    foreground: Option<String>,
+    background: Option<String>,
+    #[serde(default)]
+    accents: Vec<String>,
+    #[serde(default)]
+    bold: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colours_map_to_the_xterm_palette() {
+        assert_eq!(rgb(Color::Reset), None);
+        assert_eq!(rgb(Color::Red), Some([0xcd, 0, 0]));
+        assert_eq!(rgb(Color::Indexed(9)), rgb(Color::LightRed));
+        assert_eq!(rgb(Color::Indexed(16)), Some([0, 0, 0]));
+        assert_eq!(rgb(Color::Indexed(208)), Some([0xff, 0x87, 0x00]));
+        assert_eq!(rgb(Color::Indexed(244)), Some([0x80; 3]));
+        assert_eq!(rgb(Color::Rgb(1, 2, 3)), Some([1, 2, 3]));
+    }
+
+    #[test]
+    fn every_built_in_resolves() {
+        for name in BUILT_IN {
+            let theme = Theme::load(name).expect("built-in theme should load");
+            assert_eq!(theme.name, name);
+        }
+        let err = Theme::load("sepia").unwrap_err();
+        assert!(format!("{err:#}").contains("built-in: default, mono"));
+    }
+
+    #[test]
+    fn default_theme_keeps_the_scattered_accents() {
+        let theme = Theme::default();
+        let at = |row, col| Position { row, col, word: 0 };
+        assert_eq!(theme.style(at(0, 0)).fg, Some(Color::Magenta));
+        assert_eq!(theme.style(at(2, 5)).fg, Some(Color::Magenta));
+        assert_eq!(theme.style(at(1, 5)).fg, Some(Color::Blue));
+        assert_eq!(theme.style(at(1, 1)).fg, Some(Color::Reset));
+    }
+
+    #[test]
+    fn without_color_keeps_only_weight() {
+        let theme = Theme::load("high-contrast").unwrap().without_color();
+        let style = theme.style(Position::default());
+        assert_eq!(style.fg, Some(Color::Reset));
+        assert_eq!(style.bg, None);
+        assert!(style.add_modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn tracker_counts_rows_columns_and_words() {
+        let mut tracker = Tracker::default();
+        let positions: Vec<_> = "ab c\r\n語e\u{301}"
+            .graphemes(true)
+            .map(|g| tracker.place(g))
+            .collect();
+        let expected = [
+            (0, 0, 0),
+            (0, 1, 0),
+            (0, 2, 0),
+            (0, 3, 1),
+            (0, 4, 1),
+            (1, 0, 2),
+            (1, 1, 2),
+        ];
+        assert_eq!(positions.len(), expected.len());
+        for (pos, (row, col, word)) in positions.iter().zip(expected) {
+            assert_eq!(*pos, Position { row, col, word });
+        }
+        assert_eq!(
+            (0..6).map(|row| sweep(row, 3)).collect::<Vec<_>>(),
+            [0, 1, 2, 1, 0, 1]
+        );
+    }
+
+    #[test]
+    fn theme_files_are_parsed_and_validated() {
+        let theme = Theme::parse(
+            "ember",
+            "pattern = \"word\"\nbackground = \"#1d2021\"\naccents = [\"red\", \"#fabd2f\"]\n",
+        )
+        .unwrap();
+        assert_eq!(theme.background, Some(Color::Rgb(0x1d, 0x20, 0x21)));
+        assert_eq!(theme.accents, [Color::Red, Color::Rgb(0xfa, 0xbd, 0x2f)]);
+
+        let text = theme.styled_rows("one two\nthree", &Markup::default(), &[0..7, 8..13]);
+        assert_eq!(text.lines.len(), 2);
+        assert_eq!(text.lines[0].spans[0].style.fg, Some(Color::Red));
+        assert_eq!(text.lines[1].spans[0].style.fg, Some(Color::Red));
+
+        assert!(Theme::parse("x", "pattern = \"line\"").is_err());
+        assert!(Theme::parse("x", "foreground = \"octarine\"").is_err());
+        assert!(Theme::parse("x", "font = \"serif\"").is_err());
+    }
+}
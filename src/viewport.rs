@@ -0,0 +1,247 @@
+//! Laying a writing out on screen rows, and choosing which rows are in view.
+//!
+//! Rows are wrapped from the whole text rather than the part revealed so far,
+//! so a word never jumps to the next row half-way through being typed.
+
+use std::ops::Range;
+
+use crossterm::event::{Event, KeyCode, MouseEventKind};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::reveal::Progress;
+
+/// Rows scrolled per mouse wheel notch.
+const WHEEL_ROWS: usize = 3;
+
+/// Byte ranges of `text`, one per screen row of `width` columns.
+///
+/// Lines are broken at the last space that fits, or mid-word when a word is
+/// wider than the row. The space a line is broken at belongs to neither row.
+/// Widths are display columns, so CJK and emoji take two; right-to-left text
+/// is kept in logical order.
+pub fn wrap(text: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.trim_end_matches('\n').len();
+        let mut start = line_start;
+        let mut col = 0;
+        // Where the current row could end, and where the next would start.
+        let mut space: Option<(usize, usize)> = None;
+
+        for (i, grapheme) in text[line_start..line_end].grapheme_indices(true) {
+            let i = line_start + i;
+            let next = i + grapheme.len();
+            let columns = grapheme.width();
+            if grapheme.chars().all(char::is_whitespace) {
+                if col + columns > width {
+                    rows.push(start..i);
+                    start = next;
+                    col = 0;
+                    space = None;
+                } else {
+                    col += columns;
+                    space = Some((i, next));
+                }
+                continue;
+            }
+
+            if col + columns <= width {
+                col += columns;
+                continue;
+            }
+            match space {
+                Some((end, resume)) if end > start => {
+                    rows.push(start..end);
+                    start = resume;
+                    col = text[resume..next].width();
+                }
+                _ => {
+                    if i > start {
+                        rows.push(start..i);
+                    }
+                    start = i;
+                    col = columns;
+                }
+            }
+            space = None;
+        }
+
+        rows.push(start..line_end);
+        line_start += line.len();
+    }
+    rows
+}
+
+/// The row holding byte `offset` of the wrapped text, or the last row when
+/// the offset is past the end.
+pub fn row_of(rows: &[Range<usize>], offset: usize) -> usize {
+    rows.partition_point(|row| row.start <= offset)
+        .saturating_sub(1)
+}
+
+/// Screen row and column of the grapheme at byte `offset`, or `None` when it
+/// is a space the text was wrapped at.
+pub fn locate(text: &str, rows: &[Range<usize>], offset: usize) -> Option<(usize, usize)> {
+    let row = row_of(rows, offset);
+    let range = rows.get(row)?;
+    range
+        .contains(&offset)
+        .then(|| (row, text[range.start..offset].width()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scroll {
+    Up(usize),
+    Down(usize),
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+}
+
+/// Arrow keys, PgUp/PgDn, Home/End and the mouse wheel.
+pub fn scroll_for(event: &Event) -> Option<Scroll> {
+    match event {
+        Event::Key(key) => match key.code {
+            KeyCode::Up => Some(Scroll::Up(1)),
+            KeyCode::Down => Some(Scroll::Down(1)),
+            KeyCode::PageUp => Some(Scroll::PageUp),
+            KeyCode::PageDown => Some(Scroll::PageDown),
+            KeyCode::Home => Some(Scroll::Top),
+            KeyCode::End => Some(Scroll::Bottom),
+            _ => None,
+        },
+        Event::Mouse(mouse) => match mouse.kind {
+            MouseEventKind::ScrollUp => Some(Scroll::Up(WHEEL_ROWS)),
+            MouseEventKind::ScrollDown => Some(Scroll::Down(WHEEL_ROWS)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Which rows are on screen. It follows the reveal until scrolled by hand.
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    top: usize,
+    height: usize,
+    follow: bool,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            top: 0,
+            height: 0,
+            follow: true,
+        }
+    }
+}
+
+impl Viewport {
+    pub fn scroll(&mut self, scroll: Scroll) {
+        let page = self.height.max(1);
+        self.follow = false;
+        self.top = match scroll {
+            Scroll::Up(rows) => self.top.saturating_sub(rows),
+            Scroll::Down(rows) => self.top.saturating_add(rows),
+            Scroll::PageUp => self.top.saturating_sub(page),
+            Scroll::PageDown => self.top.saturating_add(page),
+            Scroll::Top => 0,
+            Scroll::Bottom => usize::MAX,
+        };
+    }
+
+    /// First row to show out of `rows`, `height` at a time, keeping
+    /// `cursor_row` in view while following the reveal.
+    pub fn top(&mut self, cursor_row: usize, rows: usize, height: usize) -> usize {
+        self.height = height;
+        if self.follow {
+            self.top = (cursor_row + 1).saturating_sub(height);
+        }
+        self.top = self.top.min(rows.saturating_sub(height));
+        self.top
+    }
+
+    /// [`top`](Self::top) for the reveal so far of the text wrapped into
+    /// `rows`: only the rows reached count until it is complete.
+    pub fn top_for(&mut self, rows: &[Range<usize>], progress: &Progress, height: usize) -> usize {
+        let cursor_row = row_of(rows, progress.visible().len().saturating_sub(1));
+        let total = if progress.is_complete() {
+            rows.len()
+        } else {
+            cursor_row + 1
+        };
+        self.top(cursor_row, total, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(text: &str, width: usize) -> Vec<&str> {
+        wrap(text, width)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn wrap_breaks_at_spaces_and_long_words() {
+        assert_eq!(rows("one two three", 7), ["one two", "three"]);
+        assert_eq!(rows("one two three", 8), ["one two", "three"]);
+        assert_eq!(rows("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(rows("a\n\nb\n", 10), ["a", "", "b"]);
+        assert!(rows("", 10).is_empty());
+    }
+
+    #[test]
+    fn positions_skip_wrapped_spaces() {
+        let text = "one two three";
+        let wrapped = wrap(text, 7);
+        assert_eq!(locate(text, &wrapped, 4), Some((0, 4)));
+        assert_eq!(locate(text, &wrapped, 7), None);
+        assert_eq!(locate(text, &wrapped, 9), Some((1, 1)));
+        assert_eq!(row_of(&wrapped, text.len()), 1);
+    }
+
+    #[test]
+    fn wide_and_combining_graphemes_take_their_display_width() {
+        assert_eq!(rows("日本語のテキスト", 6), ["日本語", "のテキ", "スト"]);
+        assert_eq!(rows("日本語", 5), ["日本", "語"]);
+        assert_eq!(rows("안녕 하세요", 7), ["안녕", "하세요"]);
+        assert_eq!(rows("cafe\u{301} au lait", 7), ["cafe\u{301} au", "lait"]);
+        assert_eq!(rows("👩‍👩‍👧👩‍👩‍👧", 3), ["👩‍👩‍👧", "👩‍👩‍👧"]);
+        assert_eq!(rows("שלום עולם", 5), ["שלום", "עולם"]);
+
+        let text = "雨 rain 雨";
+        let wrapped = wrap(text, 20);
+        assert_eq!(
+            locate(text, &wrapped, text.find('r').unwrap()),
+            Some((0, 3))
+        );
+        assert_eq!(
+            locate(text, &wrapped, text.rfind('雨').unwrap()),
+            Some((0, 8))
+        );
+    }
+
+    #[test]
+    fn viewport_follows_until_scrolled() {
+        let mut viewport = Viewport::default();
+        assert_eq!(viewport.top(2, 3, 5), 0);
+        assert_eq!(viewport.top(9, 10, 5), 5);
+
+        viewport.scroll(Scroll::PageUp);
+        assert_eq!(viewport.top(19, 20, 5), 0);
+        viewport.scroll(Scroll::Down(3));
+        assert_eq!(viewport.top(19, 20, 5), 3);
+        viewport.scroll(Scroll::Bottom);
+        assert_eq!(viewport.top(19, 20, 5), 15);
+    }
+}
@@ -0,0 +1,368 @@
+//! A writing's text plus the metadata from its optional front-matter header.
+//!
+//! YAML goes between `---` lines and TOML between `+++` lines:
+//!
+//! ```text
+//! ---
+//! title: If—
+//! author: Rudyard Kipling
+//! year: 1910
+//! tags: [advice, stoic]
+//! language: en
+//! license: public domain
+//! ---
+//! If you can keep your head when all about you
+//! ```
+//!
+//! Every field is optional, and a file without a header is all body. The body
+//! may carry inline [markup], and `.md` files are read as
+//! [Markdown](crate::markdown).
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::fuzzy;
+use crate::markdown;
+use crate::markup::{self, Markup};
+
+/// How a writing's text is written, told by its file extension.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Syntax {
+    #[default]
+    Plain,
+    Markdown,
+}
+
+impl Syntax {
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("md") => Syntax::Markdown,
+            _ => Syntax::Plain,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    title: Option<String>,
+    author: Option<String>,
+    year: Option<i32>,
+    #[serde(default)]
+    tags: Vec<String>,
+    language: Option<String>,
+    license: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Writing {
+    /// The name it is looked up by: the file stem, or where it came from.
+    pub name: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub year: Option<i32>,
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub license: Option<String>,
+    /// The text to unveil, without the header or markup.
+    pub body: String,
+    /// Timing and emphasis from the markup, by offset into `body`.
+    pub markup: Markup,
+    /// Markdown headings, by the offset into `body` of the section each one
+    /// opens.
+    pub headings: Vec<(usize, String)>,
+}
+
+impl Writing {
+    pub fn parse(name: &str, text: &str) -> Result<Self> {
+        Self::parse_as(name, text, Syntax::Plain)
+    }
+
+    pub fn parse_as(name: &str, text: &str, syntax: Syntax) -> Result<Self> {
+        let (front, body) = match split_front_matter(text) {
+            // In Markdown `---` is also a rule, so a block between two of
+            // them is only a header when it reads as YAML keys and values.
+            (Some(Header::Yaml(raw)), _) if syntax == Syntax::Markdown && !is_yaml_mapping(raw) => {
+                (None, text.strip_prefix('\u{feff}').unwrap_or(text))
+            }
+            split => split,
+        };
+        let meta = match front {
+            Some(Header::Yaml(raw)) => {
+                serde_yaml_ng::from_str(raw).context("invalid YAML front matter")?
+            }
+            Some(Header::Toml(raw)) => toml::from_str(raw).context("invalid TOML front matter")?,
+            None => FrontMatter::default(),
+        };
+
+        let source = body.trim_start_matches(['\r', '\n']);
+        let (title, body, markup, headings) = match syntax {
+            Syntax::Plain => {
+                let (body, markup) = markup::parse(source).context("invalid markup")?;
+                (meta.title, body, markup, Vec::new())
+            }
+            Syntax::Markdown => {
+                let document = markdown::parse(source).context("invalid markup")?;
+                let mut headings = document.headings;
+                // A leading heading titles the writing, unless the header
+                // already gave it another title.
+                let title = match (meta.title, document.title) {
+                    (Some(title), Some(heading))
+                        if fuzzy::normalize(&title) != fuzzy::normalize(&heading) =>
+                    {
+                        headings.insert(0, (0, heading));
+                        Some(title)
+                    }
+                    (title, heading) => title.or(heading),
+                };
+                (title, document.body, document.markup, headings)
+            }
+        };
+        if body.trim().is_empty() {
+            bail!("{name} contains no text to unveil");
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            title,
+            author: meta.author,
+            year: meta.year,
+            tags: meta.tags,
+            language: meta.language,
+            license: meta.license,
+            body,
+            markup,
+            headings,
+        })
+    }
+
+    /// The same writing from byte `offset` of its body on. The heading of
+    /// the section `offset` falls in still opens it.
+    pub fn starting_at(&self, offset: usize) -> Self {
+        let offset = offset.min(self.body.len());
+        let current = self.headings.iter().rposition(|(at, _)| *at <= offset);
+        Self {
+            body: self.body[offset..].to_string(),
+            markup: self.markup.starting_at(offset),
+            headings: self.headings[current.unwrap_or(0)..]
+                .iter()
+                .map(|(at, heading)| (at.saturating_sub(offset), heading.clone()))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The writing split at its headings, each part titled by the heading
+    /// that opens it. Text before the first heading, or a writing without
+    /// any, makes an untitled part.
+    pub fn sections(&self) -> Vec<Writing> {
+        let mut starts: Vec<(usize, Option<&str>)> = self
+            .headings
+            .iter()
+            .map(|(at, heading)| (*at, Some(heading.as_str())))
+            .collect();
+        if starts.first().is_none_or(|(at, _)| *at > 0) {
+            starts.insert(0, (0, None));
+        }
+
+        starts
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, heading))| {
+                let end = starts.get(i + 1).map_or(self.body.len(), |(at, _)| *at);
+                Self {
+                    name: self.name.clone(),
+                    title: heading.map(str::to_string),
+                    author: None,
+                    year: None,
+                    tags: self.tags.clone(),
+                    language: self.language.clone(),
+                    license: self.license.clone(),
+                    body: self.body[start..end].to_string(),
+                    markup: self.markup.within(start..end),
+                    headings: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Reads a writing from any file, named after its stem.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let text = crate::library::read_path(path)?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Self::parse_as(&name, &text, Syntax::of(path))
+            .with_context(|| format!("in {}", path.display()))
+    }
+
+    /// The title from the header, or else the name.
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.name)
+    }
+
+    /// "Author, year", or whichever of the two is known.
+    pub fn byline(&self) -> Option<String> {
+        match (&self.author, self.year) {
+            (Some(author), Some(year)) => Some(format!("{author}, {year}")),
+            (Some(author), None) => Some(author.clone()),
+            (None, Some(year)) => Some(year.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Lines with text on them; blank lines between stanzas don't count.
+    pub fn line_count(&self) -> usize {
+        self.body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .count()
+    }
+
+    /// Whether the header said anything worth a title card.
+    pub fn has_heading(&self) -> bool {
+        self.title.is_some() || self.author.is_some() || self.year.is_some()
+    }
+}
+
+enum Header<'a> {
+    Yaml(&'a str),
+    Toml(&'a str),
+}
+
+fn is_yaml_mapping(raw: &str) -> bool {
+    matches!(
+        serde_yaml_ng::from_str(raw),
+        Ok(serde_yaml_ng::Value::Mapping(_))
+    )
+}
+
+/// Splits off a header opened and closed by a `---` or `+++` line. A fence
+/// that is never closed opens no header, and the text is all body.
+fn split_front_matter(text: &str) -> (Option<Header<'_>>, &str) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let first_line = text.lines().next().unwrap_or_default();
+    let fence = match first_line.trim_end() {
+        "---" => "---",
+        "+++" => "+++",
+        _ => return (None, text),
+    };
+
+    let after_open = text[first_line.len()..]
+        .strip_prefix("\r\n")
+        .or_else(|| text[first_line.len()..].strip_prefix('\n'))
+        .unwrap_or_default();
+
+    let mut offset = 0;
+    for line in after_open.split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let raw = &after_open[..offset];
+            let body = &after_open[offset + line.len()..];
+            let header = match fence {
+                "---" => Header::Yaml(raw),
+                _ => Header::Toml(raw),
+            };
+            return (Some(header), body);
+        }
+        offset += line.len();
+    }
+
+    (None, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_and_toml_headers_are_parsed() {
+        let yaml = Writing::parse(
+            "if",
+            "---\ntitle: If—\nauthor: Rudyard Kipling\nyear: 1910\ntags: [advice, stoic]\n---\n\nIf you can keep your head\n",
+        )
+        .unwrap();
+        assert_eq!(yaml.title(), "If—");
+        assert_eq!(yaml.byline().as_deref(), Some("Rudyard Kipling, 1910"));
+        assert_eq!(yaml.tags, ["advice", "stoic"]);
+        assert_eq!(yaml.body, "If you can keep your head\n");
+
+        let toml = Writing::parse(
+            "invictus",
+            "+++\r\nauthor = \"William Ernest Henley\"\r\nlanguage = \"en\"\r\n+++\r\nOut of the night\r\n",
+        )
+        .unwrap();
+        assert_eq!(toml.title(), "invictus");
+        assert_eq!(toml.language.as_deref(), Some("en"));
+        assert_eq!(toml.body, "Out of the night\r\n");
+    }
+
+    #[test]
+    fn text_without_a_header_is_all_body() {
+        let plain = Writing::parse("notes", "--- not a fence\nline\n").unwrap();
+        assert!(!plain.has_heading());
+        assert_eq!(plain.body, "--- not a fence\nline\n");
+
+        let unclosed = Writing::parse("notes", "+++\nline\n").unwrap();
+        assert!(!unclosed.has_heading());
+        assert_eq!(unclosed.body, "+++\nline\n");
+    }
+
+    #[test]
+    fn markup_leaves_the_body_plain() {
+        let writing =
+            Writing::parse("x", "---\ntitle: X\n---\n{speed:90}Slowly,{/speed} *now*\n").unwrap();
+        assert_eq!(writing.body, "Slowly, now\n");
+        assert_eq!(writing.markup.speed_at(0), Some(90));
+
+        let rest = writing.starting_at(8);
+        assert_eq!(rest.body, "now\n");
+        assert_eq!(rest.markup.styles[0].range, 0..3);
+
+        let err = Writing::parse("x", "{color:red}never closed").unwrap_err();
+        assert!(format!("{err:#}").contains("invalid markup: line 1"));
+    }
+
+    #[test]
+    fn markdown_headings_become_sections() {
+        let markdown = "# Ode\n\nFirst *stanza*\n\n## Turn\n\nSecond\n";
+        let writing = Writing::parse_as("ode", markdown, Syntax::Markdown).unwrap();
+        assert_eq!(writing.title(), "Ode");
+        assert_eq!(writing.body, "First stanza\n\nSecond\n");
+
+        let sections = writing.sections();
+        assert_eq!(sections.len(), 2);
+        assert!(!sections[0].has_heading());
+        assert_eq!(sections[0].body, "First stanza\n\n");
+        assert_eq!(sections[0].markup.styles[0].range, 6..12);
+        assert_eq!(sections[1].title(), "Turn");
+        assert_eq!(sections[1].body, "Second\n");
+        assert_eq!(writing.starting_at(14).headings, [(0, "Turn".to_string())]);
+        assert_eq!(writing.starting_at(16).headings, [(0, "Turn".to_string())]);
+        assert_eq!(writing.starting_at(6).headings, [(8, "Turn".to_string())]);
+
+        let titled = format!("---\ntitle: Odes\n---\n{markdown}");
+        let writing = Writing::parse_as("ode", &titled, Syntax::Markdown).unwrap();
+        assert_eq!(writing.title(), "Odes");
+        assert_eq!(writing.sections()[0].title(), "Ode");
+        assert_eq!(Syntax::of(Path::new("ode.MD")), Syntax::Markdown);
+        assert_eq!(Writing::parse("ode", markdown).unwrap().sections().len(), 1);
+    }
+
+    #[test]
+    fn a_leading_markdown_rule_is_not_a_header() {
+        let ruled = "---\nFirst stanza here\n\n---\n\nSecond stanza\n";
+        let writing = Writing::parse_as("ruled", ruled, Syntax::Markdown).unwrap();
+        assert!(!writing.has_heading());
+        assert_eq!(writing.body, "First stanza here\n\nSecond stanza\n");
+    }
+
+    #[test]
+    fn broken_headers_are_rejected() {
+        assert!(Writing::parse("x", "---\ncolour: red\n---\nbody\n").is_err());
+        assert!(Writing::parse("x", "+++\nyear = \"soon\"\n+++\nbody\n").is_err());
+        assert!(Writing::parse("x", "---\ntitle: only a header\n---\n\n").is_err());
+    }
+}